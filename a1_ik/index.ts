@@ -1,2 +1,33 @@
-import init from 'wasm/a1_ik'
-(async () => { await init() })()
+import init, { set_max_iterations, set_tolerance, set_ccd_damping, last_result } from 'wasm/a1_ik'
+(async () => {
+  await init()
+
+  const bind = (id: string, f: (v: number) => void) => {
+    const inp = document.getElementById(id) as HTMLInputElement;
+    inp.addEventListener('change', () => {
+      const v = parseFloat(inp.value);
+      if (!isNaN(v)) {
+        try {
+          f(v);
+        } catch (e) {
+          console.error(e);
+        }
+      }
+    });
+  };
+  bind('inp_maxiter', (v) => set_max_iterations(Math.max(1, Math.round(v))));
+  bind('inp_tolerance', set_tolerance);
+  bind('inp_damping', set_ccd_damping);
+
+  const hud = document.getElementById('hud') as HTMLPreElement;
+  const drawHud = () => {
+    const r = last_result();
+    hud.textContent =
+      `iterations: ${r.iterations}\n` +
+      `error:      ${r.error.toExponential(3)}\n` +
+      `converged:  ${r.converged ? 'yes' : 'no'}`;
+    r.free();
+    requestAnimationFrame(drawHud);
+  };
+  requestAnimationFrame(drawHud);
+})()
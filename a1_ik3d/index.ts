@@ -0,0 +1,19 @@
+import init, { load_bvh, clear_bvh } from 'wasm/a1_ik3d'
+(async () => {
+  await init()
+
+  const input = document.getElementById('inp_bvh') as HTMLInputElement;
+  input.addEventListener('change', async () => {
+    const file = input.files?.[0];
+    if (!file) return;
+    try {
+      load_bvh(await file.text());
+    } catch (e) {
+      alert(`could not load ${file.name}: ${e}`);
+    }
+  });
+  document.getElementById('btn_clear_bvh')!.addEventListener('click', () => {
+    clear_bvh();
+    input.value = '';
+  });
+})()
@@ -0,0 +1,2 @@
+import init from 'wasm/m2_surface'
+(async () => { await init() })()
@@ -1,4 +1,4 @@
-import init, {start, draw, pan, update_spl } from 'wasm/m3_implicit'
+import init, {start, draw, pan, update_spl, set_function, set_model, set_method, mesh_stats } from 'wasm/m3_implicit'
 (async () => {
   await init()
 
@@ -22,6 +22,52 @@ import init, {start, draw, pan, update_spl } from 'wasm/m3_implicit'
   });
   update_spl(10);
 
+  const f_inp = document.getElementById('func') as HTMLInputElement;
+  const f_err = document.getElementById('func_error') as HTMLParagraphElement;
+  const m_sel = document.getElementById('model') as HTMLSelectElement;
+  const applyFunction = () => {
+    try {
+      set_function(f_inp.value);
+      f_err.textContent = '';
+      m_sel.value = 'expr';
+    } catch (e) {
+      f_err.textContent = `${e}`;
+    }
+  };
+  document.getElementById('btn_func')!.addEventListener('click', applyFunction);
+  f_inp.addEventListener('keydown', (event) => {
+    if (event.key === 'Enter') applyFunction();
+  });
+
+  m_sel.addEventListener('change', () => {
+    if (m_sel.value === 'expr') {
+      applyFunction();
+    } else {
+      set_model(m_sel.value);
+    }
+  });
+
+
+  const method_sel = document.getElementById('method') as HTMLSelectElement;
+  method_sel.addEventListener('change', () => {
+    set_method(method_sel.value);
+  });
+
+  const stats = document.getElementById('stats') as HTMLPreElement;
+  const showStats = () => {
+    const s = mesh_stats();
+    stats.textContent =
+      `vertices:     ${s.vertices}\n` +
+      `triangles:    ${s.triangles}\n` +
+      `min angle:    ${s.min_angle.toFixed(2)} deg\n` +
+      `mean quality: ${s.mean_quality.toFixed(3)}\n` +
+      `time:         ${s.time_ms.toFixed(1)} ms` +
+      (s.drawable ? '' : '\ntoo large to draw, lower the split');
+    s.free();
+  };
+  document.addEventListener('change', showStats);
+  document.getElementById('btn_func')!.addEventListener('click', showStats);
+  showStats();
 
   const drawFrame = () => {
     draw();
@@ -1,15 +1,133 @@
 use nalgebra_glm::{angle, rotate_vec2, Vec2};
-use web_sys::console;
+use std::f32::consts;
+use wasm_bindgen::prelude::*;
+
+mod ccd;
+mod fabrik;
+mod jacobian;
+mod obstacle;
+
+pub use ccd::Ccd;
+pub use fabrik::Fabrik;
+pub use jacobian::{DampedLeastSquares, JacobianTranspose, PseudoInverse};
+pub use obstacle::Obstacle;
 
 pub const ORIGIN: Vec2 = Vec2::new(-0.5, 0.0);
 
+// the solve has converged once every effector is closer than this to its target
+const CONVERGED_DISTANCE: f32 = 5e-3;
+
 pub struct IKArm {
     pub length: f32,
+    // relative to the parent arm, within (-PI, PI]
     pub angle: f32,
+    pub min_angle: f32,
+    pub max_angle: f32,
+    // 0: moves freely, 1: never moves
+    pub stiffness: f32,
+    // always precedes this arm in `IK::arms`, None for arms attached to the origin
+    pub parent: Option<usize>,
+}
+
+// the end of an arm that is pulled toward a target
+#[derive(Clone)]
+pub struct Effector {
+    pub arm: usize,
+    pub target: Vec2,
+    // relative priority when the targets cannot be reached at the same time
+    pub weight: f32,
+    // holds an intermediate joint in place instead of leading a branch
+    pub pinned: bool,
+}
+
+pub fn wrap_angle(a: f32) -> f32 {
+    let a = (a + consts::PI).rem_euclid(consts::TAU) - consts::PI;
+    if a <= -consts::PI {
+        a + consts::TAU
+    } else {
+        a
+    }
+}
+
+pub fn segment_distance(p: &Vec2, a: &Vec2, b: &Vec2) -> f32 {
+    let d = b - a;
+    let t = ((p - a).dot(&d) / d.norm_squared().max(1e-12)).clamp(0.0, 1.0);
+    (a + d * t - p).norm()
+}
+
+impl IKArm {
+    pub fn new(length: f32, parent: Option<usize>) -> Self {
+        Self {
+            length,
+            angle: 0.0,
+            min_angle: -consts::PI,
+            max_angle: consts::PI,
+            stiffness: 0.0,
+            parent,
+        }
+    }
+
+    // index of the joint this arm starts from in the output of `forward`, 0 is the origin
+    pub fn base(&self) -> usize {
+        self.parent.map_or(0, |p| p + 1)
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.max_angle - self.min_angle < consts::TAU - 1e-4
+    }
+
+    // rotate by `delta` scaled down by the stiffness and clamped to the limits,
+    // returns the rotation actually applied
+    pub fn rotate(&mut self, delta: f32) -> f32 {
+        let old = self.angle;
+        let angle = old + delta * (1.0 - self.stiffness);
+        if self.is_limited() {
+            // clamp before wrapping, so the joint never crosses the range it is kept out of
+            self.angle = angle.clamp(self.min_angle, self.max_angle);
+            self.angle - old
+        } else {
+            self.angle = wrap_angle(angle);
+            wrap_angle(self.angle - old)
+        }
+    }
+
+    // move toward an absolute angle, with the same constraints as `rotate`
+    pub fn rotate_to(&mut self, angle: f32) -> f32 {
+        if self.is_limited() {
+            // the way within the limits, which may be the long way round
+            self.rotate(wrap_angle(angle) - self.angle)
+        } else {
+            self.rotate(wrap_angle(angle - self.angle))
+        }
+    }
+}
+
+pub trait IkSolver {
+    // run one iteration toward the targets with the root fixed at `origin`,
+    // returns how far the end effectors moved
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32;
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct UpdateResult {
+    pub iterations: u32,
+    // largest distance from an effector to its target
+    pub error: f32,
+    // every effector, pinned ones included, ended within CONVERGED_DISTANCE of its target
+    pub converged: bool,
 }
 
 pub struct IK {
+    pub origin: Vec2,
     pub arms: Vec<IKArm>,
+    pub effectors: Vec<Effector>,
+    // bones are kept out of these
+    pub obstacles: Vec<Obstacle>,
+    pub solver: Box<dyn IkSolver>,
+    pub max_iterations: u32,
+    // stop once the effectors move less than this in an iteration
+    pub tolerance: f32,
 }
 
 fn angle_signed(v1: &Vec2, v2: &Vec2) -> f32 {
@@ -21,72 +139,264 @@ fn angle_signed(v1: &Vec2, v2: &Vec2) -> f32 {
     }
 }
 
+// whether `a` is `b` itself or one of its ancestors
+fn is_ancestor(arms: &[IKArm], a: usize, b: usize) -> bool {
+    let mut i = Some(b);
+    while let Some(j) = i {
+        if j == a {
+            return true;
+        }
+        i = arms[j].parent;
+    }
+    false
+}
+
+// angle of each arm in world space
+fn world_angles(arms: &[IKArm]) -> Vec<f32> {
+    let mut angles: Vec<f32> = Vec::with_capacity(arms.len());
+    for arm in arms {
+        let parent = arm.parent.map_or(0.0, |p| angles[p]);
+        angles.push(parent + arm.angle);
+    }
+    angles
+}
+
+// the origin followed by the end of each arm
+fn forward(origin: Vec2, arms: &[IKArm]) -> Vec<Vec2> {
+    let mut points = vec![origin];
+    for (arm, ang) in arms.iter().zip(world_angles(arms)) {
+        let base = points[arm.base()];
+        points.push(base + rotate_vec2(&Vec2::new(arm.length, 0.), ang));
+    }
+
+    points
+}
+
+// largest movement of an end effector between two poses
+fn displacement(before: &[Vec2], after: &[Vec2], effectors: &[Effector]) -> f32 {
+    effectors
+        .iter()
+        .map(|e| (after[e.arm + 1] - before[e.arm + 1]).magnitude())
+        .fold(0.0, f32::max)
+}
+
 impl IK {
-    pub fn new() -> Self {
+    // a chain of arms with sub-chains `(parent, lengths, first angle)` attached,
+    // every leaf gets an effector
+    fn build(chain: &[f32], branches: &[(usize, &[f32], f32)]) -> Self {
+        let mut arms = vec![];
+        for (i, &l) in chain.iter().enumerate() {
+            arms.push(IKArm::new(l, i.checked_sub(1)));
+        }
+        for &(parent, lengths, angle) in branches {
+            let mut parent = parent;
+            for (i, &l) in lengths.iter().enumerate() {
+                let mut arm = IKArm::new(l, Some(parent));
+                if i == 0 {
+                    arm.angle = angle;
+                }
+                parent = arms.len();
+                arms.push(arm);
+            }
+        }
+
+        let points = forward(ORIGIN, &arms);
+        let effectors = (0..arms.len())
+            .filter(|&i| !arms.iter().any(|a| a.parent == Some(i)))
+            .map(|arm| Effector {
+                arm,
+                target: points[arm + 1],
+                weight: 1.0,
+                pinned: false,
+            })
+            .collect();
+
         Self {
-            arms: vec![
-                IKArm {
-                    length: 0.3,
-                    angle: 0.0,
-                },
-                IKArm {
-                    length: 0.3,
-                    angle: 0.0,
-                },
-            ],
+            origin: ORIGIN,
+            arms,
+            effectors,
+            obstacles: vec![],
+            solver: Box::<Ccd>::default(),
+            max_iterations: 100,
+            tolerance: 1e-4,
         }
     }
 
+    pub fn new() -> Self {
+        Self::build(&[0.3, 0.3], &[])
+    }
+
+    // a palm with three fingers
+    pub fn hand() -> Self {
+        Self::build(
+            &[0.3],
+            &[
+                (0, &[0.15, 0.15], 0.6),
+                (0, &[0.15, 0.15], 0.0),
+                (0, &[0.15, 0.15], -0.6),
+            ],
+        )
+    }
+
+    // a spine with a head and two pairs of legs
+    pub fn creature() -> Self {
+        Self::build(
+            &[0.25, 0.25, 0.25],
+            &[
+                (0, &[0.2, 0.2], -2.0),
+                (0, &[0.2, 0.2], -1.2),
+                (2, &[0.2, 0.2], -2.0),
+                (2, &[0.2, 0.2], -1.2),
+                (2, &[0.1], 0.5),
+            ],
+        )
+    }
+
     pub fn render(&self) -> Vec<Vec2> {
-        let mut points = vec![ORIGIN];
-        let mut ang = 0.0;
-        for arm in &self.arms {
-            let last = points.last().unwrap();
-            ang += arm.angle;
-            points.push(last + rotate_vec2(&Vec2::new(arm.length, 0.), ang));
-        }
+        forward(self.origin, &self.arms)
+    }
 
-        points
+    pub fn world_angles(&self) -> Vec<f32> {
+        world_angles(&self.arms)
     }
 
-    fn update_step(&mut self, target: Vec2) -> f32 {
-        let points = self.render();
-        let mut end = *points.last().unwrap();
-        points[..points.len() - 1]
-            .iter()
-            .enumerate()
-            .rev()
-            .for_each(|(i, p)| {
-                let d = end - p;
-                let t = target - p;
-                let a = angle_signed(&d, &t) / 1.0;
-                self.arms[i].angle += a;
-                let dd = rotate_vec2(&d, a);
-                end = p + dd;
-            });
-        (points.last().unwrap() - end).magnitude()
-    }
-
-    pub fn update(&mut self, target: Vec2) {
-        for _ in 0..100 {
-            let d = self.update_step(target);
-            if d < 1e-4 {
+    pub fn update(&mut self) -> UpdateResult {
+        let mut iterations = 0;
+        while iterations < self.max_iterations {
+            iterations += 1;
+            // collisions enter the solver as extra weighted targets, refreshed every iteration
+            let mut effectors = self.effectors.clone();
+            effectors.extend(obstacle::contacts(
+                &self.render(),
+                &self.arms,
+                &self.obstacles,
+            ));
+            let d = self.solver.step(self.origin, &mut self.arms, &effectors);
+            if d < self.tolerance {
                 break;
             }
         }
+
+        let p = self.render();
+        let error = self
+            .effectors
+            .iter()
+            .map(|e| (p[e.arm + 1] - e.target).magnitude())
+            .fold(0.0, f32::max);
+        UpdateResult {
+            iterations,
+            error,
+            converged: error < CONVERGED_DISTANCE,
+        }
     }
 
-    pub fn add_arm(&mut self) {
-        self.arms.push(IKArm {
-            length: 0.3,
-            angle: 0.0,
+    // move every target to where its arm ends now, so that solving keeps the current pose
+    pub fn follow_pose(&mut self) {
+        let p = self.render();
+        for e in &mut self.effectors {
+            e.target = p[e.arm + 1];
+        }
+    }
+
+    // hold the end of an arm at its current position, or release it if already pinned,
+    // returns the index of the removed effector
+    pub fn toggle_pin(&mut self, arm: usize) -> Option<usize> {
+        if let Some(i) = self.effectors.iter().position(|e| e.pinned && e.arm == arm) {
+            self.effectors.remove(i);
+            return Some(i);
+        }
+        self.effectors.push(Effector {
+            arm,
+            target: self.render()[arm + 1],
+            weight: 1.0,
+            pinned: true,
         });
+        None
+    }
+
+    // append an arm at the end of the given effector, which moves to the new tip
+    pub fn add_arm(&mut self, effector: usize) {
+        if self.effectors[effector].pinned {
+            return;
+        }
+        let parent = self.effectors[effector].arm;
+        self.effectors[effector].arm = self.arms.len();
+        self.arms.push(IKArm::new(0.3, Some(parent)));
     }
 
-    pub fn pop_arm(&mut self) {
-        if self.arms.len() <= 1 {
+    // remove the arm under the given effector, unless it is the last one of its branch
+    pub fn pop_arm(&mut self, effector: usize) {
+        if self.effectors[effector].pinned {
+            return;
+        }
+        let i = self.effectors[effector].arm;
+        let Some(parent) = self.arms[i].parent else {
+            return;
+        };
+        if self.arms.iter().any(|a| a.parent == Some(i))
+            || self
+                .arms
+                .iter()
+                .enumerate()
+                .any(|(j, a)| j != i && a.parent == Some(parent))
+        {
             return;
         }
-        self.arms.pop();
+
+        self.arms.remove(i);
+        for arm in &mut self.arms {
+            arm.parent = arm.parent.map(|p| if p > i { p - 1 } else { p });
+        }
+        for e in &mut self.effectors {
+            if e.arm == i {
+                e.arm = parent;
+            } else if e.arm > i {
+                e.arm -= 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(angle: f32) -> IKArm {
+        let mut arm = IKArm::new(1.0, None);
+        arm.min_angle = -2.5;
+        arm.max_angle = 2.5;
+        arm.angle = angle;
+        arm
+    }
+
+    #[test]
+    fn limited_joint_stops_at_its_limit_across_pi() {
+        let mut arm = limited(2.4);
+        let applied = arm.rotate(1.0);
+        assert!((arm.angle - 2.5).abs() < 1e-6);
+        assert!((applied - 0.1).abs() < 1e-6);
+
+        let mut arm = limited(-2.4);
+        let applied = arm.rotate(-1.0);
+        assert!((arm.angle + 2.5).abs() < 1e-6);
+        assert!((applied + 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn limited_joint_does_not_take_the_short_way_round() {
+        // 2.4 to -2.4 is short across pi, but that arc is outside the limits
+        let mut arm = limited(2.4);
+        let applied = arm.rotate_to(-2.4);
+        assert!((arm.angle + 2.4).abs() < 1e-6);
+        assert!((applied + 4.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn free_joint_wraps() {
+        let mut arm = IKArm::new(1.0, None);
+        arm.angle = 3.0;
+        let applied = arm.rotate(0.5);
+        assert!((arm.angle - (3.5 - consts::TAU)).abs() < 1e-6);
+        assert!((applied - 0.5).abs() < 1e-6);
     }
 }
@@ -0,0 +1,40 @@
+use nalgebra_glm::Vec2;
+
+use super::{displacement, forward, is_ancestor, Effector, IKArm, IkSolver};
+
+// cyclic coordinate descent
+pub struct Ccd {
+    // each rotation is divided by this, values above 1 trade speed for less overshoot
+    pub damping: f32,
+}
+
+impl Default for Ccd {
+    fn default() -> Self {
+        Self { damping: 1.0 }
+    }
+}
+
+impl IkSolver for Ccd {
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32 {
+        let before = forward(origin, arms);
+        for i in (0..arms.len()).rev() {
+            let points = forward(origin, arms);
+            let p = points[arms[i].base()];
+
+            // the rotation minimizing the weighted squared distances of the effectors below
+            // this joint to their targets, atan2 of the summed cross and dot products
+            let (mut s, mut c) = (0.0, 0.0);
+            for e in effectors.iter().filter(|e| is_ancestor(arms, i, e.arm)) {
+                let d = points[e.arm + 1] - p;
+                let t = e.target - p;
+                s += e.weight * (d.x * t.y - d.y * t.x);
+                c += e.weight * d.dot(&t);
+            }
+            if s == 0.0 && c == 0.0 {
+                continue;
+            }
+            arms[i].rotate(f32::atan2(s, c) / self.damping);
+        }
+        displacement(&before, &forward(origin, arms), effectors)
+    }
+}
@@ -0,0 +1,62 @@
+use nalgebra_glm::{rotate_vec2, Vec2};
+
+use super::{angle_signed, displacement, forward, Effector, IKArm, IkSolver};
+
+// forward and backward reaching inverse kinematics (Aristidou & Lasenby 2011),
+// sub-bases shared by several branches are placed at the weighted centroid of their proposals
+pub struct Fabrik;
+
+impl IkSolver for Fabrik {
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32 {
+        let points = forward(origin, arms);
+        let n = arms.len();
+
+        // weighted sum of the positions proposed for the end of each arm
+        let mut pull = vec![(Vec2::zeros(), 0.0); n];
+        for e in effectors {
+            pull[e.arm].0 += e.target * e.weight;
+            pull[e.arm].1 += e.weight;
+        }
+
+        // backward: from the tips toward the origin, children come after their parents
+        for i in (0..n).rev() {
+            let (sum, w) = pull[i];
+            if w <= 0.0 {
+                continue;
+            }
+            let end = sum / w;
+            let d = points[arms[i].base()] - end;
+            let d = if d.norm() > 1e-8 {
+                d.normalize()
+            } else {
+                Vec2::new(-1.0, 0.0)
+            };
+            if let Some(p) = arms[i].parent {
+                pull[p].0 += (end + d * arms[i].length) * w;
+                pull[p].1 += w;
+            }
+        }
+
+        // forward: pin the root back to the origin and convert to relative joint angles,
+        // the limits are applied here and the resulting pose is used as the start of the next
+        // iteration. arms nothing pulls on keep their angle
+        let mut p = points.clone();
+        let mut dirs: Vec<Vec2> = Vec::with_capacity(n);
+        for i in 0..n {
+            let parent_dir = arms[i].parent.map_or(Vec2::new(1.0, 0.0), |p| dirs[p]);
+            let base = p[arms[i].base()];
+            let (sum, w) = pull[i];
+            if w > 0.0 {
+                let d = sum / w - base;
+                if d.norm() > 1e-8 {
+                    arms[i].rotate_to(angle_signed(&parent_dir, &d));
+                }
+            }
+            let dir = rotate_vec2(&parent_dir, arms[i].angle);
+            p[i + 1] = base + dir * arms[i].length;
+            dirs.push(dir);
+        }
+
+        displacement(&points, &p, effectors)
+    }
+}
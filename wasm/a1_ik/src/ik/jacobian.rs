@@ -0,0 +1,111 @@
+use nalgebra::{DMatrix, DVector};
+use nalgebra_glm::Vec2;
+
+use super::{displacement, forward, is_ancestor, Effector, IKArm, IkSolver};
+
+// the requested end effector displacement is clamped to this length per iteration,
+// otherwise the linearization breaks down for far targets
+const MAX_STEP: f32 = 0.1;
+
+// d(end effector) / d(angle): rotating joint i moves the end perpendicular to (end - p_i)
+// columns are weighted by the joint mobility, `IKArm::rotate` applies the weight again
+// so that the result is the weighted least squares step.
+// each effector contributes two rows scaled by the square root of its weight
+fn jacobian(arms: &[IKArm], points: &[Vec2], effectors: &[Effector]) -> DMatrix<f32> {
+    DMatrix::from_fn(effectors.len() * 2, arms.len(), |r, c| {
+        let e = &effectors[r / 2];
+        if !is_ancestor(arms, c, e.arm) {
+            return 0.0;
+        }
+        let d = (points[e.arm + 1] - points[arms[c].base()])
+            * (1.0 - arms[c].stiffness)
+            * e.weight.sqrt();
+        if r % 2 == 0 {
+            -d.y
+        } else {
+            d.x
+        }
+    })
+}
+
+fn error(points: &[Vec2], effectors: &[Effector]) -> DVector<f32> {
+    DVector::from_iterator(
+        effectors.len() * 2,
+        effectors.iter().flat_map(|e| {
+            let mut d = e.target - points[e.arm + 1];
+            if d.norm() > MAX_STEP {
+                d = d.normalize() * MAX_STEP;
+            }
+            d *= e.weight.sqrt();
+            [d.x, d.y]
+        }),
+    )
+}
+
+fn apply(
+    arms: &mut [IKArm],
+    points: &[Vec2],
+    effectors: &[Effector],
+    dtheta: &DVector<f32>,
+) -> f32 {
+    for (arm, d) in arms.iter_mut().zip(dtheta.iter()) {
+        arm.rotate(*d);
+    }
+    displacement(points, &forward(points[0], arms), effectors)
+}
+
+pub struct JacobianTranspose;
+
+impl IkSolver for JacobianTranspose {
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32 {
+        let points = forward(origin, arms);
+        let j = jacobian(arms, &points, effectors);
+        let e = error(&points, effectors);
+
+        // step length minimizing the linearized error (Buss 2004)
+        let jjte = &j * j.transpose() * &e;
+        let denom = jjte.dot(&jjte);
+        let alpha = if denom > 1e-12 {
+            e.dot(&jjte) / denom
+        } else {
+            0.0
+        };
+
+        apply(arms, &points, effectors, &(j.transpose() * e * alpha))
+    }
+}
+
+pub struct PseudoInverse;
+
+impl IkSolver for PseudoInverse {
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32 {
+        let points = forward(origin, arms);
+        let j = jacobian(arms, &points, effectors);
+        let e = error(&points, effectors);
+
+        let Ok(pinv) = j.pseudo_inverse(1e-4) else {
+            return 0.0;
+        };
+        apply(arms, &points, effectors, &(pinv * e))
+    }
+}
+
+pub struct DampedLeastSquares {
+    pub lambda: f32,
+}
+
+impl IkSolver for DampedLeastSquares {
+    fn step(&self, origin: Vec2, arms: &mut [IKArm], effectors: &[Effector]) -> f32 {
+        let points = forward(origin, arms);
+        let j = jacobian(arms, &points, effectors);
+        let e = error(&points, effectors);
+
+        // J^T (J J^T + lambda^2 I)^-1 e
+        let n = j.nrows();
+        let jjt = &j * j.transpose() + DMatrix::identity(n, n) * (self.lambda * self.lambda);
+        let Some(chol) = jjt.cholesky() else {
+            return 0.0;
+        };
+        apply(arms, &points, effectors, &(j.transpose() * chol.solve(&e)))
+    }
+}
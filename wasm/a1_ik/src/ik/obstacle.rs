@@ -0,0 +1,128 @@
+use nalgebra_glm::{rotate_vec2, Vec2};
+use std::f32::consts;
+
+use super::{Effector, IKArm};
+
+// penalty weight of a contact relative to the user's targets
+const CONTACT_WEIGHT: f32 = 10.0;
+// bones are pushed this far beyond the surface so that they do not graze it
+const SKIN: f32 = 0.01;
+
+pub enum Obstacle {
+    Circle { center: Vec2, radius: f32 },
+    // convex, counterclockwise
+    Polygon(Vec<Vec2>),
+}
+
+impl Obstacle {
+    pub fn regular_polygon(center: Vec2, radius: f32, n: usize) -> Self {
+        Obstacle::Polygon(
+            (0..n)
+                .map(|i| {
+                    let a = i as f32 / n as f32 * consts::TAU + consts::FRAC_PI_2;
+                    center + rotate_vec2(&Vec2::new(radius, 0.0), a)
+                })
+                .collect(),
+        )
+    }
+
+    // signed distance, negative inside, and the outward direction.
+    // for polygons this is the distance to the farthest edge line,
+    // which is exact inside and only keeps the sign outside
+    pub fn distance(&self, p: &Vec2) -> (f32, Vec2) {
+        match self {
+            Obstacle::Circle { center, radius } => {
+                let d = p - center;
+                let n = if d.norm() > 1e-8 {
+                    d.normalize()
+                } else {
+                    Vec2::new(0.0, 1.0)
+                };
+                (d.norm() - radius, n)
+            }
+            Obstacle::Polygon(v) => (0..v.len())
+                .map(|i| {
+                    let e = v[(i + 1) % v.len()] - v[i];
+                    let n = Vec2::new(e.y, -e.x).normalize();
+                    ((p - v[i]).dot(&n), n)
+                })
+                .fold((f32::NEG_INFINITY, Vec2::zeros()), |a, b| {
+                    if b.0 > a.0 {
+                        b
+                    } else {
+                        a
+                    }
+                }),
+        }
+    }
+
+    pub fn contains(&self, p: &Vec2) -> bool {
+        self.distance(p).0 < 0.0
+    }
+
+    // deepest point of the segment a-b as a fraction along it, and the vector pushing it out
+    pub fn penetration(&self, a: &Vec2, b: &Vec2) -> Option<(f32, Vec2)> {
+        // the distance is convex along a line, ternary search for its minimum
+        let f = |t: f32| self.distance(&(a + (b - a) * t));
+        let (mut lo, mut hi) = (0.0, 1.0);
+        for _ in 0..32 {
+            let m1 = lo + (hi - lo) / 3.0;
+            let m2 = hi - (hi - lo) / 3.0;
+            if f(m1).0 < f(m2).0 {
+                hi = m2;
+            } else {
+                lo = m1;
+            }
+        }
+        let t = (lo + hi) * 0.5;
+        let (d, n) = f(t);
+        if d < SKIN {
+            Some((t, n * (SKIN - d)))
+        } else {
+            None
+        }
+    }
+
+    // outline, counterclockwise
+    pub fn outline(&self) -> Vec<Vec2> {
+        match self {
+            Obstacle::Circle { center, radius } => {
+                const SEGMENTS: usize = 32;
+                (0..SEGMENTS)
+                    .map(|i| {
+                        let a = i as f32 / SEGMENTS as f32 * consts::TAU;
+                        center + Vec2::new(a.cos(), a.sin()) * *radius
+                    })
+                    .collect()
+            }
+            Obstacle::Polygon(v) => v.clone(),
+        }
+    }
+}
+
+// temporary effectors pulling the ends of penetrating arms out of the obstacles.
+// rotating an arm about its base moves a point at fraction t by t times the end,
+// so the end is asked to move by push / t
+pub fn contacts(points: &[Vec2], arms: &[IKArm], obstacles: &[Obstacle]) -> Vec<Effector> {
+    let mut r = vec![];
+    for (i, arm) in arms.iter().enumerate() {
+        let a = points[arm.base()];
+        let b = points[i + 1];
+        for o in obstacles {
+            let Some((t, push)) = o.penetration(&a, &b) else {
+                continue;
+            };
+            // a base inside the obstacle is the parent's contact
+            if t < 0.05 {
+                continue;
+            }
+            r.push(Effector {
+                arm: i,
+                target: b + push / t,
+                weight: CONTACT_WEIGHT,
+                pinned: false,
+            });
+        }
+    }
+    r
+}
@@ -2,11 +2,15 @@ use common::vao::MyVAO;
 use glm::{rotate_vec2, Vec2};
 use nalgebra_glm as glm;
 
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, f32::consts, rc::Rc};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{WebGl2RenderingContext as GL, *};
 
 mod ik;
+mod motion;
+mod physics;
+mod skin;
+mod timeline;
 
 struct Scene {
     gl: Rc<GL>,
@@ -18,9 +22,76 @@ struct Scene {
     mvp_location: WebGlUniformLocation,
 
     ik: ik::IK,
+    mode: Mode,
+    // index into `ik.arms` rotated by the mouse in FK mode
+    bone: Option<usize>,
+    // restarted from the current pose when entering the physics mode
+    physics: physics::Physics,
+    // index into `ik.effectors` moved by the mouse
+    selected: usize,
+    // the mouse moves the origin instead of a target
+    moving_root: bool,
+    // what a click on the canvas places, None to drag targets
+    placing: Option<Shape>,
+
+    // solve every frame with bounded joint speed instead of on every input
+    animated: bool,
+    motion: motion::Motion,
+
+    solver_name: String,
+    ccd_damping: f32,
+    last_result: ik::UpdateResult,
+
+    show_skin: bool,
+    // rebuilt when the bones change
+    skin: Option<skin::Skin>,
+
+    timeline: timeline::Timeline,
+    time: f32,
+    recording: bool,
+    // time of the next key to record
+    rec_next: f32,
+    playing: bool,
+    // the mouse is on the timeline, moving the selected key or the playhead
+    scrubbing: bool,
+    selected_key: Option<usize>,
 }
 
-const MAX_POINTS: usize = 1024;
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    // drag targets and let the solver pose the arms
+    Ik,
+    // rotate the bones directly
+    Fk,
+    // the arms swing under gravity and can be grabbed
+    Physics,
+}
+
+#[derive(Clone, Copy)]
+enum Shape {
+    Circle,
+    Polygon,
+}
+
+const MAX_POINTS: usize = 4096;
+const MAX_OBSTACLES: usize = 16;
+
+// the timeline is drawn along the bottom edge of the canvas
+const TIMELINE_TOP: f32 = -0.86;
+const TIMELINE_Y: f32 = -0.93;
+const TIMELINE_LEFT: f32 = -0.95;
+const TIMELINE_WIDTH: f32 = 1.9;
+// seconds
+const TIMELINE_SPAN: f32 = 10.0;
+const REC_INTERVAL: f32 = 0.1;
+
+fn time_to_x(t: f32) -> f32 {
+    TIMELINE_LEFT + TIMELINE_WIDTH * t / TIMELINE_SPAN
+}
+
+fn x_to_time(x: f32) -> f32 {
+    ((x - TIMELINE_LEFT) / TIMELINE_WIDTH * TIMELINE_SPAN).clamp(0.0, TIMELINE_SPAN)
+}
 impl Scene {
     fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
         canvas.set_width(CANVAS_SIZE);
@@ -57,6 +128,30 @@ impl Scene {
             mvp_location,
 
             ik: ik::IK::new(),
+            mode: Mode::Ik,
+            bone: None,
+            physics: physics::Physics::new(&ik::IK::new()),
+            selected: 0,
+            moving_root: false,
+            placing: None,
+
+            animated: false,
+            motion: motion::Motion::new(consts::PI),
+
+            solver_name: "ccd".to_string(),
+            ccd_damping: 1.0,
+            last_result: ik::UpdateResult::default(),
+
+            show_skin: true,
+            skin: None,
+
+            timeline: timeline::Timeline::default(),
+            time: 0.0,
+            recording: false,
+            rec_next: 0.0,
+            playing: false,
+            scrubbing: false,
+            selected_key: None,
         };
 
         r.update();
@@ -66,30 +161,293 @@ impl Scene {
 
     fn update(&mut self) {
         let p = self.ik.render();
-        let n = p.len();
 
-        let mut v = vec![];
+        let mut v = self
+            .ik
+            .arms
+            .iter()
+            .enumerate()
+            .flat_map(|(i, arm)| {
+                let v0 = p[arm.base()];
+                let v3 = p[i + 1];
+                let d = v3 - v0;
+                let v1 = v0 + rotate_vec2(&d, 0.5) * 0.2;
+                let v2 = v0 + rotate_vec2(&d, -0.5) * 0.2;
+                [
+                    v0.x, v0.y, 0.0, v1.x, v1.y, 0.0, v2.x, v2.y, 0.0, v3.x, v3.y, 0.0,
+                ]
+            })
+            .collect::<Vec<_>>();
 
-        v.extend(p.windows(2).flat_map(|v| {
-            let v0 = v[0];
-            let v3 = v[1];
-            let d = v3 - v0;
-            let v1 = v0 + rotate_vec2(&d, 0.5) * 0.2;
-            let v2 = v0 + rotate_vec2(&d, -0.5) * 0.2;
-            [
-                v0.x, v0.y, 0.0, v1.x, v1.y, 0.0, v2.x, v2.y, 0.0, v3.x, v3.y, 0.0,
-            ]
-        }));
-
-        let mut c = p
+        // stiffer arms are drawn redder, the bone selected in FK mode green
+        let mut c = self
+            .ik
+            .arms
             .iter()
-            .flat_map(|_| [1.0, 1.0, 1.0, 1.0])
+            .enumerate()
+            .flat_map(|(i, arm)| {
+                if self.mode == Mode::Fk && self.bone == Some(i) {
+                    [0.3, 1.0, 0.3, 1.0].repeat(4)
+                } else {
+                    [1.0, 1.0 - arm.stiffness, 1.0 - arm.stiffness, 1.0].repeat(4)
+                }
+            })
             .collect::<Vec<_>>();
 
-        let mut idx = (0..(p.len() - 1) as u16)
+        let mut idx = (0..self.ik.arms.len() as u16)
             .flat_map(|i| [i * 4, i * 4 + 3, i * 4 + 1, i * 4, i * 4 + 2, i * 4 + 3])
             .collect::<Vec<_>>();
+
+        // the skinned mesh between the arms and the obstacles
+        if self.show_skin {
+            if !self.skin.as_ref().is_some_and(|s| s.matches(&self.ik)) {
+                self.skin = Some(skin::Skin::new(&self.ik));
+            }
+            let skin = self.skin.as_ref().unwrap();
+            let base = (v.len() / 3) as u16;
+            v.extend(skin.deform(&self.ik).iter().flat_map(|p| [p.x, p.y, 0.05]));
+            c.extend(skin.colors.iter().flatten());
+            idx.extend(skin.indices.iter().map(|i| base + i));
+        }
+
+        // obstacles as triangle fans, slightly behind the arms
+        for o in &self.ik.obstacles {
+            let base = (v.len() / 3) as u16;
+            let outline = o.outline();
+            let n = outline.len() as u16;
+            v.extend(outline.iter().flat_map(|p| [p.x, p.y, 0.1]));
+            c.extend([0.5, 0.2, 0.2, 1.0].repeat(outline.len()));
+            idx.extend((1..n - 1).flat_map(|k| [base, base + k, base + k + 1]));
+        }
         self.vao_tri.send_data(&v, &c, &idx);
+
+        // joint limits as arcs around each joint
+        const ARC_RADIUS: f32 = 0.08;
+        const ARC_SEGMENTS: u16 = 16;
+        let mut v = vec![];
+        let mut c = vec![];
+        let mut idx = vec![];
+        let angles = self.ik.world_angles();
+        for arm in &self.ik.arms {
+            let parent_angle = arm.parent.map_or(0.0, |p| angles[p]);
+            let p = p[arm.base()];
+            if arm.is_limited() {
+                let base = (v.len() / 3) as u16;
+                let color = [1.0, 1.0 - arm.stiffness, 0.0, 1.0];
+
+                v.extend([p.x, p.y, 0.0]);
+                c.extend(color);
+                for k in 0..=ARC_SEGMENTS {
+                    let t = k as f32 / ARC_SEGMENTS as f32;
+                    let a = parent_angle + arm.min_angle + (arm.max_angle - arm.min_angle) * t;
+                    v.extend([p.x + a.cos() * ARC_RADIUS, p.y + a.sin() * ARC_RADIUS, 0.0]);
+                    c.extend(color);
+                }
+
+                idx.extend([base, base + 1, base + ARC_SEGMENTS + 1, base]);
+                idx.extend((1..=ARC_SEGMENTS).flat_map(|k| [base + k, base + k + 1]));
+            }
+        }
+
+        // targets as crosses and pins as squares sized by their weight,
+        // the dragged one in yellow
+        let mut marker = |t: Vec2, r: f32, color: [f32; 4], square: bool| {
+            let base = (v.len() / 3) as u16;
+            v.extend([t.x - r, t.y - r, 0.0, t.x + r, t.y - r, 0.0]);
+            v.extend([t.x + r, t.y + r, 0.0, t.x - r, t.y + r, 0.0]);
+            c.extend(color.repeat(4));
+            if square {
+                idx.extend([0, 1, 1, 2, 2, 3, 3, 0].map(|k| base + k));
+            } else {
+                idx.extend([0, 2, 1, 3].map(|k| base + k));
+            }
+        };
+        marker(self.ik.origin, 0.02, [0.0, 1.0, 1.0, 1.0], true);
+        // targets are not used outside of IK mode, pins still hold in physics mode
+        let effectors = self
+            .ik
+            .effectors
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| self.mode == Mode::Ik || (self.mode == Mode::Physics && e.pinned));
+        for (i, e) in effectors {
+            let color = if i == self.selected && !self.moving_root && self.mode == Mode::Ik {
+                [1.0, 1.0, 0.0, 1.0]
+            } else if e.pinned {
+                [1.0, 0.3, 0.3, 1.0]
+            } else {
+                [0.5, 0.5, 0.5, 1.0]
+            };
+            marker(e.target, 0.03 * e.weight.sqrt(), color, e.pinned);
+        }
+        if let Some(i) = self
+            .physics
+            .grabbed()
+            .filter(|_| self.mode == Mode::Physics)
+        {
+            marker(self.ik.render()[i], 0.03, [1.0, 1.0, 0.0, 1.0], false);
+        }
+
+        // timeline with its keys and the playhead
+        let mut line = |a: [f32; 2], b: [f32; 2], color: [f32; 4]| {
+            let base = (v.len() / 3) as u16;
+            v.extend([a[0], a[1], 0.0, b[0], b[1], 0.0]);
+            c.extend(color.repeat(2));
+            idx.extend([base, base + 1]);
+        };
+        line(
+            [TIMELINE_LEFT, TIMELINE_Y],
+            [TIMELINE_LEFT + TIMELINE_WIDTH, TIMELINE_Y],
+            [0.5, 0.5, 0.5, 1.0],
+        );
+        for (i, k) in self.timeline.keys.iter().enumerate() {
+            let x = time_to_x(k.time);
+            let color = if self.selected_key == Some(i) {
+                [1.0, 1.0, 0.0, 1.0]
+            } else {
+                [0.8, 0.8, 0.8, 1.0]
+            };
+            line([x, TIMELINE_Y - 0.02], [x, TIMELINE_Y + 0.02], color);
+        }
+        let x = time_to_x(self.time);
+        let color = if self.recording {
+            [1.0, 0.0, 0.0, 1.0]
+        } else {
+            [0.0, 1.0, 0.0, 1.0]
+        };
+        line([x, TIMELINE_Y - 0.04], [x, TIMELINE_Y + 0.04], color);
+
+        self.vao_lin.send_data(&v, &c, &idx);
+    }
+
+    // snap to the solution, in animated mode `tick` gets there over time
+    fn solve(&mut self) {
+        if !self.animated && self.mode == Mode::Ik {
+            self.last_result = self.ik.update();
+        }
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if self.playing {
+            let duration = self.timeline.duration();
+            if duration > 0.0 {
+                self.time = (self.time + dt) % duration;
+                self.timeline.apply(self.time, &mut self.ik);
+                self.update();
+            }
+            return;
+        }
+
+        if self.animated && self.mode == Mode::Ik {
+            self.last_result = self.motion.advance(&mut self.ik, dt);
+        }
+        if self.mode == Mode::Physics {
+            self.physics.step(&mut self.ik, dt);
+        }
+        if self.recording {
+            self.time += dt;
+            if self.time >= self.rec_next {
+                self.timeline.set_key(self.time, &self.ik);
+                self.rec_next = self.time + REC_INTERVAL;
+            }
+            if self.time >= TIMELINE_SPAN {
+                self.set_recording(false);
+            }
+        }
+        if self.animated || self.recording || self.mode == Mode::Physics {
+            self.update();
+        }
+    }
+
+    fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+        self.rec_next = self.time;
+        if recording {
+            self.playing = false;
+        }
+        self.show_transport();
+        self.update();
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+        if playing {
+            self.recording = false;
+        }
+        self.show_transport();
+        self.update();
+    }
+
+    // write the record and play state into their checkboxes, which also changes when
+    // the other one is turned on or recording reaches the end of the timeline
+    fn show_transport(&self) {
+        let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+            return;
+        };
+        for (id, checked) in [("chk_record", self.recording), ("chk_play", self.playing)] {
+            if let Some(e) = document
+                .get_element_by_id(id)
+                .and_then(|e| e.dyn_into::<HtmlInputElement>().ok())
+            {
+                e.set_checked(checked);
+            }
+        }
+    }
+
+    fn add_key(&mut self) {
+        self.selected_key = Some(self.timeline.set_key(self.time, &self.ik));
+        self.update();
+    }
+
+    fn delete_key(&mut self) {
+        if let Some(i) = self.selected_key.take() {
+            self.timeline.keys.remove(i);
+        }
+        self.update();
+    }
+
+    fn export(&mut self) {
+        self.show_export(&self.timeline.to_json(&self.ik));
+    }
+
+    fn export_bvh(&mut self) {
+        self.show_export(&self.timeline.to_bvh(&self.ik).write());
+    }
+
+    fn show_export(&self, text: &str) {
+        if let Some(e) = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.get_element_by_id("json"))
+            .and_then(|e| e.dyn_into::<HtmlTextAreaElement>().ok())
+        {
+            e.set_value(text);
+        }
+    }
+
+    // select a key or move the playhead, then drag either of them
+    fn timeline_handler(&mut self, event: &web_sys::MouseEvent, p: &Vec2) {
+        let t = x_to_time(p.x);
+        if event.type_() == "mousedown" {
+            const PICK_RADIUS: f32 = 8.0 / CANVAS_SIZE as f32 * 2.0;
+            self.selected_key = self
+                .timeline
+                .keys
+                .iter()
+                .position(|k| (time_to_x(k.time) - p.x).abs() < PICK_RADIUS);
+        }
+        if let Some(i) = self.selected_key {
+            self.selected_key = Some(self.timeline.move_key(i, t));
+        }
+        self.time = t;
+        self.timeline.apply(t, &mut self.ik);
+        self.update();
+    }
+
+    fn set_max_speed(&mut self, deg: f32) {
+        if deg.is_nan() || deg <= 0.0 {
+            return;
+        }
+        self.motion.max_speed = deg.to_radians();
     }
 
     fn draw(&self) {
@@ -107,6 +465,8 @@ impl Scene {
 
     fn mouse_handler(&mut self, event: web_sys::MouseEvent) {
         if event.buttons() != 1 {
+            // let go of the arm, it keeps swinging with the speed it was dragged at
+            self.physics.release();
             return;
         }
 
@@ -117,16 +477,159 @@ impl Scene {
 
         // todo!();
 
-        self.ik.update(p);
+        if event.type_() == "mousedown" {
+            self.scrubbing = p.y < TIMELINE_TOP;
+        }
+        if self.scrubbing {
+            self.timeline_handler(&event, &p);
+            return;
+        }
+
+        if let Some(shape) = self.placing {
+            if event.type_() == "mousedown" {
+                self.toggle_obstacle(&p, shape);
+            }
+            return;
+        }
+
+        if self.mode == Mode::Fk {
+            self.fk_handler(&event, &p);
+            return;
+        }
+
+        const PICK_RADIUS: f32 = 16.0 / CANVAS_SIZE as f32 * 2.0;
+        if event.type_() == "mousedown" {
+            if event.shift_key() {
+                self.toggle_pin(&p);
+                return;
+            }
+            self.moving_root = (self.ik.origin - p).magnitude() < PICK_RADIUS;
+            if !self.moving_root && self.mode == Mode::Ik {
+                self.selected = self.nearest_effector(&p);
+            }
+        }
+        if self.moving_root {
+            self.ik.origin = p;
+        } else if self.mode == Mode::Physics {
+            // grab the nearest joint, then follow the mouse while the button is held
+            let grabbed = self.physics.grabbed().or_else(|| {
+                let ps = self.ik.render();
+                (1..ps.len())
+                    .map(|i| (i, (ps[i] - p).magnitude()))
+                    .filter(|(_, d)| *d < PICK_RADIUS * 2.0)
+                    .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                    .map(|(i, _)| i)
+            });
+            if let Some(i) = grabbed {
+                self.physics.grab(i, p);
+            }
+            return;
+        } else {
+            self.ik.effectors[self.selected].target = p;
+        }
+        self.solve();
+
+        self.update();
+    }
+
+    // pick the bone under the cursor, then turn it around its base toward the cursor
+    fn fk_handler(&mut self, event: &web_sys::MouseEvent, p: &Vec2) {
+        let ps = self.ik.render();
+        if event.type_() == "mousedown" {
+            const PICK_RADIUS: f32 = 16.0 / CANVAS_SIZE as f32 * 2.0;
+            self.bone = self
+                .ik
+                .arms
+                .iter()
+                .enumerate()
+                .map(|(i, arm)| (i, ik::segment_distance(p, &ps[arm.base()], &ps[i + 1])))
+                .filter(|(_, d)| *d < PICK_RADIUS)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(i, _)| i);
+            self.show_bone();
+            self.update();
+            return;
+        }
+
+        let Some(i) = self.bone else {
+            return;
+        };
+        let d = p - ps[self.ik.arms[i].base()];
+        if d.magnitude() < 1e-3 {
+            return;
+        }
+        let parent_angle = self.ik.arms[i]
+            .parent
+            .map_or(0.0, |j| self.ik.world_angles()[j]);
+        self.set_bone_angle(d.y.atan2(d.x) - parent_angle);
+        self.show_bone();
+    }
 
+    // relative to the parent, clamped to the limits but ignoring the stiffness
+    fn set_bone_angle(&mut self, angle: f32) {
+        let Some(arm) = self.bone.map(|i| &mut self.ik.arms[i]) else {
+            return;
+        };
+        arm.angle = ik::wrap_angle(angle).clamp(arm.min_angle, arm.max_angle);
+        self.update();
+    }
+
+    fn set_bone_length(&mut self, length: f32) {
+        let Some(arm) = self.bone.map(|i| &mut self.ik.arms[i]) else {
+            return;
+        };
+        arm.length = length.max(0.1);
+        self.update();
+    }
+
+    // write the selected bone into the angle and length inputs
+    fn show_bone(&self) {
+        let Some(document) = web_sys::window().and_then(|w| w.document()) else {
+            return;
+        };
+        let arm = self.bone.map(|i| &self.ik.arms[i]);
+        let values = [
+            ("inp_bone_angle", arm.map(|a| a.angle.to_degrees())),
+            ("inp_bone_length", arm.map(|a| a.length)),
+        ];
+        for (id, value) in values {
+            if let Some(e) = document
+                .get_element_by_id(id)
+                .and_then(|e| e.dyn_into::<HtmlInputElement>().ok())
+            {
+                e.set_value(&value.map_or(String::new(), |v| format!("{:.2}", v)));
+                e.set_disabled(value.is_none());
+            }
+        }
+    }
+
+    fn set_mode(&mut self, name: &str) {
+        self.mode = match name {
+            "ik" => Mode::Ik,
+            "fk" => Mode::Fk,
+            "physics" => Mode::Physics,
+            _ => {
+                return;
+            }
+        };
+        self.physics = physics::Physics::new(&self.ik);
+        // the targets catch up with the bones moved by hand, so the solver keeps the pose
+        self.ik.follow_pose();
+        self.show_bone();
         self.update();
     }
 
     fn scroll_handler(&mut self, event: web_sys::WheelEvent) {
-        let delta: f64 = event.delta_y();
+        // some browsers turn shift+wheel into horizontal scrolling
+        let delta: f64 = if event.delta_y().abs() >= 1. {
+            event.delta_y()
+        } else {
+            event.delta_x()
+        };
         if delta.abs() < 1. {
             return;
         }
+        event.prevent_default();
         // todo!();
         let p = Vec2::new(
             (event.offset_x() as f32 / CANVAS_SIZE as f32) * 2. - 1.,
@@ -135,38 +638,206 @@ impl Scene {
 
         // console::log_1(&format!("delta: {}", delta).into());
 
+        let delta = delta.signum() as f32;
+        if event.ctrl_key() {
+            let i = self.nearest_effector(&p);
+            let e = &mut self.ik.effectors[i];
+            e.weight = (e.weight * 1.25f32.powf(-delta)).clamp(0.1, 10.0);
+            self.solve();
+            self.update();
+            return;
+        }
+
         let ps = self.ik.render();
-        let e = ps
-            .windows(2)
-            .map(|v| ((v[1] + v[0]) * 0.5 - p).magnitude_squared());
+        let e = self
+            .ik
+            .arms
+            .iter()
+            .enumerate()
+            .map(|(i, arm)| ((ps[i + 1] + ps[arm.base()]) * 0.5 - p).magnitude_squared());
         let nearest = e
             .enumerate()
             .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
             .unwrap()
             .0;
 
-        let l = &mut self.ik.arms[nearest].length;
-        *l = (*l - 0.1 * delta.signum() as f32).max(0.1);
+        let arm = &mut self.ik.arms[nearest];
+        if event.shift_key() {
+            // symmetric limit range, 10 degrees per step
+            let half = ((arm.max_angle - arm.min_angle) * 0.5 - delta * 10f32.to_radians())
+                .clamp(5f32.to_radians(), consts::PI);
+            arm.min_angle = -half;
+            arm.max_angle = half;
+            arm.angle = arm.angle.clamp(arm.min_angle, arm.max_angle);
+        } else if event.alt_key() {
+            arm.stiffness = (arm.stiffness - 0.1 * delta).clamp(0.0, 0.9);
+        } else {
+            arm.length = (arm.length - 0.1 * delta).max(0.1);
+        }
+
+        if self.bone == Some(nearest) {
+            self.show_bone();
+        }
+        self.update();
+    }
+
+    // pin or release the joint nearest to `p`
+    fn toggle_pin(&mut self, p: &Vec2) {
+        let nearest = self.ik.render()[1..]
+            .iter()
+            .map(|q| (q - p).magnitude_squared())
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+            .0;
+        if let Some(i) = self.ik.toggle_pin(nearest) {
+            if self.selected >= i && self.selected > 0 {
+                self.selected -= 1;
+            }
+        }
+        self.update();
+    }
+
+    fn nearest_effector(&self, p: &Vec2) -> usize {
+        self.ik
+            .effectors
+            .iter()
+            .map(|e| (e.target - p).magnitude_squared())
+            .enumerate()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap()
+            .0
+    }
 
+    // remove the obstacle under `p`, or place a new one there
+    fn toggle_obstacle(&mut self, p: &Vec2, shape: Shape) {
+        let obstacles = &mut self.ik.obstacles;
+        if let Some(i) = obstacles.iter().position(|o| o.contains(p)) {
+            obstacles.remove(i);
+        } else if obstacles.len() < MAX_OBSTACLES {
+            obstacles.push(match shape {
+                Shape::Circle => ik::Obstacle::Circle {
+                    center: *p,
+                    radius: 0.1,
+                },
+                Shape::Polygon => ik::Obstacle::regular_polygon(*p, 0.12, 5),
+            });
+        }
+        self.solve();
         self.update();
     }
 
+    fn set_placing(&mut self, name: &str) {
+        self.placing = match name {
+            "circle" => Some(Shape::Circle),
+            "polygon" => Some(Shape::Polygon),
+            _ => None,
+        };
+    }
+
+    fn set_skeleton(&mut self, name: &str) {
+        let ik = match name {
+            "chain" => ik::IK::new(),
+            "hand" => ik::IK::hand(),
+            "creature" => ik::IK::creature(),
+            _ => {
+                return;
+            }
+        };
+        // keep the scene and solver settings
+        let old = std::mem::replace(&mut self.ik, ik);
+        self.ik.solver = old.solver;
+        self.ik.obstacles = old.obstacles;
+        self.ik.max_iterations = old.max_iterations;
+        self.ik.tolerance = old.tolerance;
+        self.skin = None;
+        self.solve();
+        self.bone = None;
+        self.show_bone();
+        self.selected = 0;
+        self.moving_root = false;
+        self.update();
+    }
+
+    fn set_solver(&mut self, name: &str) {
+        self.ik.solver = match name {
+            "ccd" => Box::new(ik::Ccd {
+                damping: self.ccd_damping,
+            }),
+            "fabrik" => Box::new(ik::Fabrik),
+            "jt" => Box::new(ik::JacobianTranspose),
+            "pinv" => Box::new(ik::PseudoInverse),
+            "dls" => Box::new(ik::DampedLeastSquares { lambda: 0.1 }),
+            _ => {
+                return;
+            }
+        };
+        self.solver_name = name.to_string();
+    }
+
     fn addrmv(&mut self, d: i32) {
         if d < 0 {
             for _ in 0..d.abs() {
-                self.ik.pop_arm();
+                self.ik.pop_arm(self.selected);
+            }
+            if self.bone.is_some_and(|i| i >= self.ik.arms.len()) {
+                self.bone = None;
+                self.show_bone();
             }
         } else {
             for _ in 0..d {
-                self.ik.add_arm();
+                self.ik.add_arm(self.selected);
             }
         }
+        self.solve();
         self.update();
     }
 }
 
 const CANVAS_SIZE: u32 = 1024;
 
+common::scene_slot!(Scene);
+
+#[wasm_bindgen]
+pub fn set_max_iterations(n: u32) -> Result<(), JsValue> {
+    with_scene(|scene| {
+        scene.ik.max_iterations = n.max(1);
+        scene.solve();
+        scene.update();
+    })
+}
+
+#[wasm_bindgen]
+pub fn set_tolerance(tolerance: f32) -> Result<(), JsValue> {
+    if tolerance.is_nan() || tolerance <= 0.0 {
+        return Err("tolerance must be positive".into());
+    }
+    with_scene(|scene| {
+        scene.ik.tolerance = tolerance;
+        scene.solve();
+        scene.update();
+    })
+}
+
+#[wasm_bindgen]
+pub fn set_ccd_damping(damping: f32) -> Result<(), JsValue> {
+    if damping.is_nan() || damping < 1.0 {
+        return Err("damping must be at least 1".into());
+    }
+    with_scene(|scene| {
+        scene.ccd_damping = damping;
+        if scene.solver_name == "ccd" {
+            scene.set_solver("ccd");
+        }
+    })
+}
+
+// diagnostics of the latest solve
+#[wasm_bindgen]
+pub fn last_result() -> Result<ik::UpdateResult, JsValue> {
+    with_scene(|scene| scene.last_result)
+}
+
 #[wasm_bindgen(start)]
 pub fn start() -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
@@ -178,6 +849,8 @@ pub fn start() -> Result<(), JsValue> {
         .dyn_into::<HtmlCanvasElement>()?;
 
     let scene = Rc::new(RefCell::new(Scene::new(&canvas)?));
+    scene.borrow().show_bone();
+    set_scene(scene.clone());
 
     // mousemove handler
     let scene_ = scene.clone();
@@ -186,6 +859,7 @@ pub fn start() -> Result<(), JsValue> {
     }) as Box<dyn FnMut(_)>);
     canvas.add_event_listener_with_callback("mousemove", handler.as_ref().unchecked_ref())?;
     canvas.add_event_listener_with_callback("mousedown", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("mouseup", handler.as_ref().unchecked_ref())?;
     handler.forget();
 
     let scene_ = scene.clone();
@@ -217,10 +891,101 @@ pub fn start() -> Result<(), JsValue> {
     btn_rmv.add_event_listener_with_callback("click", handler.as_ref().unchecked_ref())?;
     handler.forget();
 
+    // timeline buttons
+    type Action = fn(&mut Scene);
+    let actions: [(&str, Action); 4] = [
+        ("btn_key", Scene::add_key),
+        ("btn_delkey", Scene::delete_key),
+        ("btn_export", Scene::export),
+        ("btn_export_bvh", Scene::export_bvh),
+    ];
+    for (id, action) in actions {
+        let btn = document
+            .get_element_by_id(id)
+            .ok_or(format!("{} not found", id))?
+            .dyn_into::<HtmlButtonElement>()?;
+        let scene_ = scene.clone();
+        let handler = Closure::wrap(Box::new(move |_: web_sys::MouseEvent| {
+            action(&mut scene_.borrow_mut());
+        }) as Box<dyn FnMut(_)>);
+        btn.add_event_listener_with_callback("click", handler.as_ref().unchecked_ref())?;
+        handler.forget();
+    }
+
+    // input handlers
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        let targ = if let Some(e) = event.target() {
+            e
+        } else {
+            return;
+        };
+        let (targid, val) = if let Some(e) = targ.dyn_ref::<HtmlInputElement>() {
+            (e.name(), e.value())
+        } else if let Some(e) = targ.dyn_ref::<HtmlSelectElement>() {
+            (e.name(), e.value())
+        } else {
+            return;
+        };
+        let checked = targ
+            .dyn_ref::<HtmlInputElement>()
+            .is_some_and(|e| e.checked());
+
+        match targid.as_str() {
+            "mode" => scene_.borrow_mut().set_mode(&val),
+            "solver" => scene_.borrow_mut().set_solver(&val),
+            "skeleton" => scene_.borrow_mut().set_skeleton(&val),
+            "place" => scene_.borrow_mut().set_placing(&val),
+            "animate" => scene_.borrow_mut().animated = checked,
+            "record" => scene_.borrow_mut().set_recording(checked),
+            "play" => scene_.borrow_mut().set_playing(checked),
+            "skin" => {
+                let mut scene = scene_.borrow_mut();
+                scene.show_skin = checked;
+                scene.update();
+            }
+            "smooth" => scene_.borrow_mut().motion.smoothing = checked.then_some(10.0),
+            "bone_angle" => {
+                if let Ok(d) = val.parse::<f32>() {
+                    let mut scene = scene_.borrow_mut();
+                    scene.set_bone_angle(d.to_radians());
+                    scene.show_bone();
+                }
+            }
+            "bone_length" => {
+                if let Ok(l) = val.parse() {
+                    let mut scene = scene_.borrow_mut();
+                    scene.set_bone_length(l);
+                    scene.show_bone();
+                }
+            }
+            "maxspeed" => {
+                if let Ok(d) = val.parse() {
+                    scene_.borrow_mut().set_max_speed(d);
+                }
+            }
+            _ => {}
+        }
+    }) as Box<dyn FnMut(_)>);
+    document.add_event_listener_with_callback("change", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let performance = web_sys::window()
+        .unwrap()
+        .performance()
+        .ok_or("performance not available")?;
+    let mut last_time = performance.now();
+
     let closure = Rc::new(RefCell::new(None));
     let closure_ = closure.clone();
     *closure_.borrow_mut() = Some(Closure::<dyn FnMut() -> Result<i32, JsValue>>::new(
         move || {
+            let now = performance.now();
+            // cap the step so that a hidden tab does not jump on return
+            let dt = (((now - last_time) / 1000.0) as f32).min(0.1);
+            last_time = now;
+
+            scene.borrow_mut().tick(dt);
             scene.borrow().draw();
             request_animation_frame(closure.borrow().as_ref().unwrap())
         },
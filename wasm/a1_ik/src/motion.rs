@@ -0,0 +1,66 @@
+use crate::ik::{wrap_angle, UpdateResult, IK};
+
+// moves the arms toward the solved pose over time instead of snapping to it
+pub struct Motion {
+    // radians per second, per joint
+    pub max_speed: f32,
+    // natural frequency of the critically damped spring, None to move straight at max_speed
+    pub smoothing: Option<f32>,
+    velocity: Vec<f32>,
+}
+
+impl Motion {
+    pub fn new(max_speed: f32) -> Self {
+        Self {
+            max_speed,
+            smoothing: None,
+            velocity: vec![],
+        }
+    }
+
+    // returns the result of solving for the pose being approached
+    pub fn advance(&mut self, ik: &mut IK, dt: f32) -> UpdateResult {
+        if dt <= 0.0 {
+            return UpdateResult::default();
+        }
+        // arms were added or removed, start from rest
+        if self.velocity.len() != ik.arms.len() {
+            self.velocity = vec![0.0; ik.arms.len()];
+        }
+
+        let current = ik.arms.iter().map(|a| a.angle).collect::<Vec<_>>();
+        let result = ik.update();
+
+        let max_step = self.max_speed * dt;
+        for ((arm, v), angle) in ik.arms.iter_mut().zip(&mut self.velocity).zip(current) {
+            let goal = arm.angle;
+            // limited joints stay within their range, free ones take the short way round
+            let x = if arm.is_limited() {
+                angle - goal
+            } else {
+                wrap_angle(angle - goal)
+            };
+
+            let step = if let Some(omega) = self.smoothing {
+                // exact integration of x'' = -2 omega x' - omega^2 x over dt
+                let e = (-omega * dt).exp();
+                let temp = (*v + omega * x) * dt;
+                *v = (*v - omega * temp) * e;
+                (x + temp) * e - x
+            } else {
+                -x
+            };
+
+            let clamped = step.clamp(-max_step, max_step);
+            if self.smoothing.is_none() || clamped != step {
+                *v = clamped / dt;
+            }
+            arm.angle = if arm.is_limited() {
+                (angle + clamped).clamp(arm.min_angle, arm.max_angle)
+            } else {
+                wrap_angle(angle + clamped)
+            };
+        }
+        result
+    }
+}
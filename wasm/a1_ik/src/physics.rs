@@ -0,0 +1,151 @@
+use nalgebra_glm::{rotate_vec2, Vec2};
+
+use crate::ik::{wrap_angle, IK};
+
+// scene units per second squared
+const GRAVITY: Vec2 = Vec2::new(0.0, -4.0);
+// fraction of the velocity kept per second
+const DRAG: f32 = 0.6;
+const SUBSTEP: f32 = 1.0 / 240.0;
+const ITERATIONS: usize = 8;
+
+// position based simulation of the joints, the arms are posed from the result.
+// points are indexed like the output of `IK::render`, 0 is the origin
+pub struct Physics {
+    prev: Vec<Vec2>,
+    // point held by the mouse and where it is dragged to
+    grab: Option<(usize, Vec2)>,
+}
+
+impl Physics {
+    // start at rest in the current pose
+    pub fn new(ik: &IK) -> Self {
+        Self {
+            prev: ik.render(),
+            grab: None,
+        }
+    }
+
+    pub fn grab(&mut self, point: usize, to: Vec2) {
+        self.grab = Some((point, to));
+    }
+
+    pub fn release(&mut self) {
+        self.grab = None;
+    }
+
+    pub fn grabbed(&self) -> Option<usize> {
+        self.grab.map(|g| g.0)
+    }
+
+    pub fn step(&mut self, ik: &mut IK, dt: f32) {
+        // arms were added or removed, start from rest
+        if self.prev.len() != ik.arms.len() + 1 {
+            self.prev = ik.render();
+        }
+        if self.grab.is_some_and(|g| g.0 > ik.arms.len()) {
+            self.grab = None;
+        }
+
+        // the origin, pinned joints and the grabbed point move only with the user
+        let mut fixed = vec![false; ik.arms.len() + 1];
+        fixed[0] = true;
+        for e in ik.effectors.iter().filter(|e| e.pinned) {
+            fixed[e.arm + 1] = true;
+        }
+        if let Some((i, _)) = self.grab {
+            fixed[i] = true;
+        }
+
+        let n = ((dt / SUBSTEP).ceil() as usize).max(1);
+        let h = dt / n as f32;
+        let keep = DRAG.powf(h);
+        let mut p = ik.render();
+        let grab_start = self.grab.map(|(i, _)| p[i]);
+        for k in 0..n {
+            // verlet integration of the free points
+            for (i, (p, prev)) in p.iter_mut().zip(&mut self.prev).enumerate() {
+                let old = *p;
+                if !fixed[i] {
+                    *p += (*p - *prev) * keep + GRAVITY * h * h;
+                }
+                *prev = old;
+            }
+            if let (Some((i, to)), Some(start)) = (self.grab, grab_start) {
+                // spread the drag over the substeps so that a release keeps its speed
+                p[i] = start.lerp(&to, (k + 1) as f32 / n as f32);
+            }
+            p[0] = ik.origin;
+            for e in ik.effectors.iter().filter(|e| e.pinned) {
+                p[e.arm + 1] = e.target;
+            }
+
+            for _ in 0..ITERATIONS {
+                project(ik, &fixed, &mut p);
+            }
+        }
+
+        pose(ik, &p);
+        // the posed points can differ from the simulated ones where the constraints could not
+        // all be met, carry the velocities over to where the arms really are
+        for ((prev, p), r) in self.prev.iter_mut().zip(&p).zip(ik.render()) {
+            *prev += r - p;
+        }
+    }
+}
+
+// distances, joint limits and obstacles, one pass
+fn project(ik: &IK, fixed: &[bool], p: &mut [Vec2]) {
+    for (i, arm) in ik.arms.iter().enumerate() {
+        let (a, b) = (arm.base(), i + 1);
+        let d = p[b] - p[a];
+        let len = d.magnitude().max(1e-6);
+        let corr = d * ((len - arm.length) / len);
+        match (fixed[a], fixed[b]) {
+            (true, true) => {}
+            (true, false) => p[b] -= corr,
+            (false, true) => p[a] += corr,
+            (false, false) => {
+                p[a] += corr * 0.5;
+                p[b] -= corr * 0.5;
+            }
+        }
+    }
+
+    // parents come first, so their world angles are final when a child is clamped
+    let mut world = vec![0.0; ik.arms.len()];
+    for (i, arm) in ik.arms.iter().enumerate() {
+        let d = p[i + 1] - p[arm.base()];
+        let angle = d.y.atan2(d.x);
+        let parent = arm.parent.map_or(0.0, |j| world[j]);
+        let local = wrap_angle(angle - parent);
+        let clamped = local.clamp(arm.min_angle, arm.max_angle);
+        world[i] = parent + clamped;
+        if clamped != local && !fixed[i + 1] {
+            p[i + 1] = p[arm.base()] + rotate_vec2(&d, clamped - local);
+        }
+    }
+
+    for (i, q) in p.iter_mut().enumerate() {
+        if fixed[i] {
+            continue;
+        }
+        for o in &ik.obstacles {
+            let (d, n) = o.distance(q);
+            if d < 0.0 {
+                *q -= n * d;
+            }
+        }
+    }
+}
+
+// write the simulated points back as arm angles, keeping the lengths exact
+fn pose(ik: &mut IK, p: &[Vec2]) {
+    let mut world = vec![0.0; ik.arms.len()];
+    for (i, arm) in ik.arms.iter_mut().enumerate() {
+        let d = p[i + 1] - p[arm.base()];
+        let parent = arm.parent.map_or(0.0, |j| world[j]);
+        arm.angle = wrap_angle(d.y.atan2(d.x) - parent).clamp(arm.min_angle, arm.max_angle);
+        world[i] = parent + arm.angle;
+    }
+}
@@ -0,0 +1,146 @@
+use nalgebra_glm::{rotate_vec2, Vec2};
+
+use crate::ik::{segment_distance, IK};
+
+// rings of vertices along each bone
+const RINGS: usize = 4;
+const WIDTH: f32 = 0.06;
+// each vertex follows at most this many bones
+const MAX_INFLUENCES: usize = 4;
+
+// a strip of triangles around the bones, deformed by linear blend skinning
+pub struct Skin {
+    // (parent, length) of the bones it was bound to
+    topology: Vec<(Option<usize>, f32)>,
+    // bone frames (base, world angle) in the bind pose
+    bind: Vec<(Vec2, f32)>,
+
+    rest: Vec<Vec2>,
+    pub colors: Vec<[f32; 4]>,
+    pub indices: Vec<u16>,
+    weights: Vec<Vec<(usize, f32)>>,
+}
+
+fn topology(ik: &IK) -> Vec<(Option<usize>, f32)> {
+    ik.arms.iter().map(|a| (a.parent, a.length)).collect()
+}
+
+fn frames(ik: &IK) -> Vec<(Vec2, f32)> {
+    let p = ik.render();
+    ik.arms
+        .iter()
+        .zip(ik.world_angles())
+        .map(|(arm, a)| (p[arm.base()], a))
+        .collect()
+}
+
+impl Skin {
+    // build the mesh around the current pose and bind it there
+    pub fn new(ik: &IK) -> Self {
+        let points = ik.render();
+        let angles = ik.world_angles();
+
+        // depth of each bone for tapering and coloring
+        let mut depth = vec![0.0; ik.arms.len()];
+        for (i, arm) in ik.arms.iter().enumerate() {
+            depth[i] = arm.parent.map_or(0.0, |p| depth[p] + 1.0);
+        }
+        let max_depth = depth.iter().fold(1.0f32, |a, b| a.max(*b + 1.0));
+
+        let mut rest = vec![];
+        let mut colors = vec![];
+        let mut indices = vec![];
+        // first vertex of the last ring of each bone
+        let mut last_ring = vec![0u16; ik.arms.len()];
+        for (i, arm) in ik.arms.iter().enumerate() {
+            let base = points[arm.base()];
+            let dir = rotate_vec2(&Vec2::new(1.0, 0.0), angles[i]);
+            let normal = Vec2::new(-dir.y, dir.x);
+
+            let first = rest.len() as u16;
+            for k in 0..=RINGS {
+                let t = k as f32 / RINGS as f32;
+                let s = (depth[i] + t) / max_depth;
+                let w = WIDTH * (1.0 - 0.7 * s);
+                let c = base + dir * arm.length * t;
+                rest.extend([c + normal * w, c - normal * w]);
+                let color = [0.2 + 0.6 * s, 0.4, 0.8 - 0.6 * s, 1.0];
+                colors.extend([color, color]);
+            }
+            last_ring[i] = first + RINGS as u16 * 2;
+
+            // bridge from the end of the parent, then along the bone
+            let rings = arm
+                .parent
+                .map(|p| last_ring[p])
+                .into_iter()
+                .chain((0..=RINGS as u16).map(|k| first + k * 2))
+                .collect::<Vec<_>>();
+            for r in rings.windows(2) {
+                let (a, b) = (r[0], r[1]);
+                indices.extend([a, a + 1, b + 1, a, b + 1, b]);
+            }
+        }
+
+        // counterclockwise in the bind pose, the arms are drawn with back faces culled
+        for t in indices.chunks_mut(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| rest[t[k] as usize]);
+            let d1 = b - a;
+            let d2 = c - a;
+            if d1.x * d2.y - d1.y * d2.x < 0.0 {
+                t.swap(1, 2);
+            }
+        }
+
+        // inverse distance weights to the nearest bones
+        let weights = rest
+            .iter()
+            .map(|v| {
+                let mut w = ik
+                    .arms
+                    .iter()
+                    .enumerate()
+                    .map(|(j, arm)| {
+                        let d = segment_distance(v, &points[arm.base()], &points[j + 1]);
+                        (j, 1.0 / (d * d + 1e-4).powi(2))
+                    })
+                    .collect::<Vec<_>>();
+                w.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+                w.truncate(MAX_INFLUENCES);
+                let sum = w.iter().map(|x| x.1).sum::<f32>();
+                w.iter().map(|&(j, x)| (j, x / sum)).collect()
+            })
+            .collect();
+
+        Self {
+            topology: topology(ik),
+            bind: frames(ik),
+            rest,
+            colors,
+            indices,
+            weights,
+        }
+    }
+
+    // false once bones were added, removed or resized, the mesh should be rebuilt then
+    pub fn matches(&self, ik: &IK) -> bool {
+        self.topology == topology(ik)
+    }
+
+    pub fn deform(&self, ik: &IK) -> Vec<Vec2> {
+        let frames = frames(ik);
+        self.rest
+            .iter()
+            .zip(&self.weights)
+            .map(|(v, w)| {
+                w.iter()
+                    .map(|&(j, x)| {
+                        let (b0, a0) = self.bind[j];
+                        let (b1, a1) = frames[j];
+                        (b1 + rotate_vec2(&(v - b0), a1 - a0)) * x
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+}
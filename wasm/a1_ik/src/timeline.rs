@@ -0,0 +1,194 @@
+use common::bvh::{self, Bvh, Channel};
+
+use crate::ik::{wrap_angle, IK};
+
+pub struct Key {
+    pub time: f32,
+    pub angles: Vec<f32>,
+}
+
+// keyframes of the arm angles, sorted by time
+#[derive(Default)]
+pub struct Timeline {
+    pub keys: Vec<Key>,
+}
+
+// keys closer than this in time are replaced instead of added
+const KEY_EPS: f32 = 1e-3;
+
+// BVH export, in centimeters at 30 frames per second
+const BVH_SCALE: f32 = 100.0;
+const BVH_FRAME_TIME: f32 = 1.0 / 30.0;
+
+impl Timeline {
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |k| k.time)
+    }
+
+    // insert or replace the key at `time` with the current pose, returns its index
+    pub fn set_key(&mut self, time: f32, ik: &IK) -> usize {
+        // keys for another skeleton are of no use anymore
+        if self
+            .keys
+            .first()
+            .is_some_and(|k| k.angles.len() != ik.arms.len())
+        {
+            self.keys.clear();
+        }
+
+        let angles = ik.arms.iter().map(|a| a.angle).collect();
+        match self.keys.iter().position(|k| k.time > time - KEY_EPS) {
+            Some(i) if (self.keys[i].time - time).abs() < KEY_EPS => {
+                self.keys[i].angles = angles;
+                i
+            }
+            Some(i) => {
+                self.keys.insert(i, Key { time, angles });
+                i
+            }
+            None => {
+                self.keys.push(Key { time, angles });
+                self.keys.len() - 1
+            }
+        }
+    }
+
+    // move a key in time, returns its new index
+    pub fn move_key(&mut self, i: usize, time: f32) -> usize {
+        let mut key = self.keys.remove(i);
+        key.time = time.max(0.0);
+        let j = self
+            .keys
+            .iter()
+            .position(|k| k.time > key.time)
+            .unwrap_or(self.keys.len());
+        self.keys.insert(j, key);
+        j
+    }
+
+    // interpolated angles, each joint turning the short way around
+    pub fn sample(&self, time: f32) -> Option<Vec<f32>> {
+        let first = self.keys.first()?;
+        let Some(i) = self.keys.iter().position(|k| k.time > time) else {
+            return Some(self.keys.last().unwrap().angles.clone());
+        };
+        if i == 0 {
+            return Some(first.angles.clone());
+        }
+
+        let (k0, k1) = (&self.keys[i - 1], &self.keys[i]);
+        let t = (time - k0.time) / (k1.time - k0.time).max(1e-6);
+        Some(
+            k0.angles
+                .iter()
+                .zip(&k1.angles)
+                .map(|(a, b)| wrap_angle(a + wrap_angle(b - a) * t))
+                .collect(),
+        )
+    }
+
+    // pose the arms at `time`, the limits still apply
+    pub fn apply(&self, time: f32, ik: &mut IK) {
+        let Some(angles) = self.sample(time) else {
+            return;
+        };
+        if angles.len() != ik.arms.len() {
+            return;
+        }
+        for (arm, a) in ik.arms.iter_mut().zip(angles) {
+            arm.angle = a.clamp(arm.min_angle, arm.max_angle);
+        }
+    }
+
+    pub fn to_json(&self, ik: &IK) -> String {
+        let arms = ik
+            .arms
+            .iter()
+            .map(|a| {
+                let parent = a.parent.map_or("null".to_string(), |p| p.to_string());
+                format!(r#"{{"length":{},"parent":{}}}"#, a.length, parent)
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        let keys = self
+            .keys
+            .iter()
+            .map(|k| {
+                let angles = k
+                    .angles
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!(r#"{{"time":{},"angles":[{}]}}"#, k.time, angles)
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"duration":{},"arms":[{}],"keys":[{}]}}"#,
+            self.duration(),
+            arms,
+            keys
+        )
+    }
+
+    // the rig as a BVH clip in the xy plane, rotating around z.
+    // the timeline is sampled if it has keys for this rig, otherwise the current pose
+    pub fn to_bvh(&self, ik: &IK) -> Bvh {
+        let mut joints = vec![bvh::Joint {
+            name: "Origin".to_string(),
+            parent: None,
+            offset: [ik.origin.x * BVH_SCALE, ik.origin.y * BVH_SCALE, 0.0],
+            channels: vec![
+                Channel::Xposition,
+                Channel::Yposition,
+                Channel::Zposition,
+                Channel::Zrotation,
+                Channel::Xrotation,
+                Channel::Yrotation,
+            ],
+            end_site: None,
+        }];
+        for (i, arm) in ik.arms.iter().enumerate() {
+            // each arm is a joint at its base, children sit at the end of their parent
+            let offset = arm
+                .parent
+                .map_or([0.0; 3], |p| [ik.arms[p].length * BVH_SCALE, 0.0, 0.0]);
+            let leaf = !ik.arms.iter().any(|a| a.parent == Some(i));
+            joints.push(bvh::Joint {
+                name: format!("Arm{}", i),
+                parent: Some(arm.parent.map_or(0, |p| p + 1)),
+                offset,
+                channels: vec![Channel::Zrotation, Channel::Xrotation, Channel::Yrotation],
+                end_site: leaf.then_some([arm.length * BVH_SCALE, 0.0, 0.0]),
+            });
+        }
+
+        let poses = if self
+            .keys
+            .first()
+            .is_some_and(|k| k.angles.len() == ik.arms.len())
+        {
+            let n = (self.duration() / BVH_FRAME_TIME).round() as usize + 1;
+            (0..n)
+                .filter_map(|i| self.sample(i as f32 * BVH_FRAME_TIME))
+                .collect()
+        } else {
+            vec![ik.arms.iter().map(|a| a.angle).collect::<Vec<_>>()]
+        };
+        let frames = poses
+            .into_iter()
+            .map(|angles| {
+                let mut frame = vec![0.0; 6];
+                frame.extend(angles.iter().flat_map(|a| [a.to_degrees(), 0.0, 0.0]));
+                frame
+            })
+            .collect();
+
+        Bvh {
+            joints,
+            frame_time: BVH_FRAME_TIME,
+            frames,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use common::bvh::{Bvh, Channel};
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::Vec3;
+
+// the clip is scaled to this height and stood on the ground
+const HEIGHT: f32 = 2.0;
+
+// a BVH skeleton played back from its channels
+pub struct Clip {
+    bvh: Bvh,
+    scale: f32,
+    shift: Vec3,
+}
+
+struct Pose {
+    // local translation and rotation of each joint, in the file's units
+    translations: Vec<Vec3>,
+    rotations: Vec<UnitQuaternion<f32>>,
+}
+
+impl Clip {
+    pub fn new(bvh: Bvh) -> Result<Self, String> {
+        if bvh.frames.is_empty() {
+            return Err("the clip has no frames".to_string());
+        }
+        let mut r = Self {
+            bvh,
+            scale: 1.0,
+            shift: Vec3::zeros(),
+        };
+
+        let points = r.segments(0.0).into_iter().flat_map(|(a, b)| [a, b]);
+        let (min, max) = points.fold(
+            (Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY)),
+            |(min, max), p| (min.inf(&p), max.sup(&p)),
+        );
+        let extent = (max - min).max();
+        if extent > 1e-6 {
+            r.scale = HEIGHT / extent;
+        }
+        let center = (min + max) * 0.5;
+        r.shift = -Vec3::new(center.x, min.y, center.z) * r.scale;
+        Ok(r)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.bvh.frames.len() as f32 * self.bvh.frame_time
+    }
+
+    // local translation and rotation of each joint in one frame
+    fn frame(&self, frame: &[f32]) -> Pose {
+        let offsets = self.bvh.channel_offsets();
+        let mut translations = vec![];
+        let mut rotations = vec![];
+        for (joint, &start) in self.bvh.joints.iter().zip(&offsets) {
+            let mut t = Vec3::from(joint.offset);
+            let mut q = UnitQuaternion::identity();
+            for (c, v) in joint.channels.iter().zip(&frame[start..]) {
+                let axis = match c {
+                    Channel::Xposition => {
+                        t.x += v;
+                        continue;
+                    }
+                    Channel::Yposition => {
+                        t.y += v;
+                        continue;
+                    }
+                    Channel::Zposition => {
+                        t.z += v;
+                        continue;
+                    }
+                    Channel::Xrotation => Vec3::x_axis(),
+                    Channel::Yrotation => Vec3::y_axis(),
+                    Channel::Zrotation => Vec3::z_axis(),
+                };
+                // rotations apply in the order they are listed
+                q *= UnitQuaternion::from_axis_angle(&axis, v.to_radians());
+            }
+            translations.push(t);
+            rotations.push(q);
+        }
+        Pose {
+            translations,
+            rotations,
+        }
+    }
+
+    // local pose at `time`, interpolated between frames and looping
+    fn pose(&self, time: f32) -> Pose {
+        let n = self.bvh.frames.len();
+        let f = (time / self.bvh.frame_time.max(1e-6)).rem_euclid(n as f32);
+        let i = (f as usize).min(n - 1);
+        let s = f - i as f32;
+
+        let a = self.frame(&self.bvh.frames[i]);
+        let b = self.frame(&self.bvh.frames[(i + 1) % n]);
+        Pose {
+            translations: a
+                .translations
+                .iter()
+                .zip(&b.translations)
+                .map(|(x, y)| x.lerp(y, s))
+                .collect(),
+            rotations: a
+                .rotations
+                .iter()
+                .zip(&b.rotations)
+                .map(|(x, y)| x.slerp(y, s))
+                .collect(),
+        }
+    }
+
+    // bones as line segments in world space, including the end sites
+    pub fn segments(&self, time: f32) -> Vec<(Vec3, Vec3)> {
+        let pose = self.pose(time);
+        let mut positions: Vec<Vec3> = vec![];
+        let mut world: Vec<UnitQuaternion<f32>> = vec![];
+        let mut r = vec![];
+        for (i, joint) in self.bvh.joints.iter().enumerate() {
+            let (p, q) = match joint.parent {
+                Some(j) => (
+                    positions[j] + world[j] * pose.translations[i],
+                    world[j] * pose.rotations[i],
+                ),
+                None => (pose.translations[i], pose.rotations[i]),
+            };
+            if let Some(j) = joint.parent {
+                r.push((positions[j], p));
+            }
+            if let Some(end) = joint.end_site {
+                r.push((p, p + q * Vec3::from(end)));
+            }
+            positions.push(p);
+            world.push(q);
+        }
+
+        r.into_iter()
+            .map(|(a, b)| (a * self.scale + self.shift, b * self.scale + self.shift))
+            .collect()
+    }
+}
@@ -0,0 +1,167 @@
+use nalgebra::{Quaternion, Unit, UnitQuaternion, Vector3};
+use nalgebra_glm::Vec3;
+use std::f32::consts;
+
+pub const ORIGIN: Vec3 = Vec3::new(0.0, 0.0, 0.0);
+
+// bones extend along their local +Y axis
+pub const BONE_AXIS: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+#[derive(Clone, Copy)]
+pub enum Joint {
+    Free,
+    // 1-DOF rotation around an axis given in the parent frame
+    Hinge { axis: Vec3, min: f32, max: f32 },
+    // swing of the bone axis within a cone and twist around it
+    Ball { max_swing: f32, max_twist: f32 },
+}
+
+pub struct Bone {
+    pub length: f32,
+    // relative to the parent bone
+    pub rotation: UnitQuaternion<f32>,
+    pub joint: Joint,
+}
+
+pub struct IK {
+    pub bones: Vec<Bone>,
+}
+
+fn wrap_angle(a: f32) -> f32 {
+    (a + consts::PI).rem_euclid(consts::TAU) - consts::PI
+}
+
+// split q into swing * twist where twist is a rotation around `axis`
+pub fn swing_twist(
+    q: &UnitQuaternion<f32>,
+    axis: &Vec3,
+) -> (UnitQuaternion<f32>, UnitQuaternion<f32>) {
+    let v = q.imag();
+    let p = axis * v.dot(axis);
+    let twist = Quaternion::new(q.w, p.x, p.y, p.z);
+    let twist = if twist.norm() < 1e-6 {
+        UnitQuaternion::identity()
+    } else {
+        UnitQuaternion::new_normalize(twist)
+    };
+    (q * twist.inverse(), twist)
+}
+
+// signed rotation angle of a twist quaternion around `axis`
+fn twist_angle(twist: &UnitQuaternion<f32>, axis: &Vec3) -> f32 {
+    wrap_angle(2.0 * twist.imag().dot(axis).atan2(twist.w))
+}
+
+impl Joint {
+    pub fn constrain(&self, q: &UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+        match *self {
+            Joint::Free => *q,
+            Joint::Hinge { axis, min, max } => {
+                let (_, twist) = swing_twist(q, &axis);
+                let a = twist_angle(&twist, &axis).clamp(min, max);
+                UnitQuaternion::from_axis_angle(&Unit::new_normalize(axis), a)
+            }
+            Joint::Ball {
+                max_swing,
+                max_twist,
+            } => {
+                let (swing, twist) = swing_twist(q, &BONE_AXIS);
+
+                let swing = match swing.axis_angle() {
+                    Some((axis, a)) if a > max_swing => {
+                        UnitQuaternion::from_axis_angle(&axis, max_swing)
+                    }
+                    _ => swing,
+                };
+
+                let a = twist_angle(&twist, &BONE_AXIS).clamp(-max_twist, max_twist);
+                let twist = UnitQuaternion::from_axis_angle(&Unit::new_normalize(BONE_AXIS), a);
+
+                swing * twist
+            }
+        }
+    }
+}
+
+impl IK {
+    pub fn new() -> Self {
+        let mut r = Self { bones: vec![] };
+        for _ in 0..4 {
+            r.add_bone();
+        }
+        r
+    }
+
+    // joint positions and world rotations of each bone
+    pub fn forward(&self) -> (Vec<Vec3>, Vec<UnitQuaternion<f32>>) {
+        let mut points = vec![ORIGIN];
+        let mut rotations = Vec::with_capacity(self.bones.len());
+        let mut rot = UnitQuaternion::identity();
+        for bone in &self.bones {
+            rot *= bone.rotation;
+            let last = points.last().unwrap();
+            points.push(last + rot * (BONE_AXIS * bone.length));
+            rotations.push(rot);
+        }
+        (points, rotations)
+    }
+
+    pub fn render(&self) -> Vec<Vec3> {
+        self.forward().0
+    }
+
+    // one pass of cyclic coordinate descent, returns how far the end effector moved
+    fn update_step(&mut self, target: Vec3) -> f32 {
+        let (points, rotations) = self.forward();
+        let start = *points.last().unwrap();
+
+        for i in (0..self.bones.len()).rev() {
+            let (points, _) = self.forward();
+            let end = points.last().unwrap();
+            let p = points[i];
+
+            let d = end - p;
+            let t = target - p;
+            let Some(q) = UnitQuaternion::rotation_between(&Vector3::from(d), &Vector3::from(t))
+            else {
+                continue;
+            };
+
+            // world rotation q applied to bone i, expressed in its parent frame
+            // bones before i are untouched so far in this pass
+            let parent = if i > 0 {
+                rotations[i - 1]
+            } else {
+                UnitQuaternion::identity()
+            };
+            let local = parent.inverse() * q * parent * self.bones[i].rotation;
+            self.bones[i].rotation = self.bones[i].joint.constrain(&local);
+        }
+
+        (self.render().last().unwrap() - start).magnitude()
+    }
+
+    pub fn update(&mut self, target: Vec3) {
+        for _ in 0..100 {
+            let d = self.update_step(target);
+            if d < 1e-4 {
+                break;
+            }
+        }
+    }
+
+    pub fn add_bone(&mut self) {
+        self.bones.push(Bone {
+            length: 0.4,
+            rotation: UnitQuaternion::identity(),
+            joint: Joint::Free,
+        });
+    }
+
+    pub fn pop_bone(&mut self) {
+        if self.bones.len() <= 1 {
+            return;
+        }
+        self.bones.pop();
+    }
+}
@@ -0,0 +1,417 @@
+use common::{camera::OrbitCamera, vao::MyVAO};
+use glm::{Vec2, Vec3};
+use nalgebra::UnitQuaternion;
+use nalgebra_glm as glm;
+use std::{cell::RefCell, f32::consts, rc::Rc};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{WebGl2RenderingContext as GL, *};
+
+mod clip;
+mod ik;
+
+struct Scene {
+    gl: Rc<GL>,
+    program: WebGlProgram,
+
+    vao_lin: MyVAO,
+    vao_pts: MyVAO,
+
+    mvp_location: WebGlUniformLocation,
+
+    camera: OrbitCamera,
+
+    ik: ik::IK,
+    target: Vec3,
+    dragging: bool,
+
+    // imported motion and its playback time
+    clip: Option<clip::Clip>,
+    clip_time: f32,
+}
+
+const MAX_POINTS: usize = 4096;
+const MAX_BONES: usize = 32;
+// segments of an imported clip, the rest of the line buffer holds the grid and the chain
+const MAX_CLIP_SEGMENTS: usize = 1024;
+impl Scene {
+    fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
+        canvas.set_width(CANVAS_SIZE);
+        canvas.set_height(CANVAS_SIZE);
+        let gl = canvas
+            .get_context("webgl2")?
+            .ok_or("Failed to get WebGl2RenderingContext")?
+            .dyn_into::<GL>()?;
+        let gl = Rc::new(gl);
+
+        let program = common::create_program(
+            &gl,
+            include_str!("shader/vertex.glsl"),
+            include_str!("shader/fragment.glsl"),
+        )?;
+
+        gl.enable(GL::DEPTH_TEST);
+        gl.depth_func(GL::LEQUAL);
+
+        let vao_lin = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 2)?;
+        let vao_pts = MyVAO::new(gl.clone(), MAX_BONES + 2, MAX_BONES + 2)?;
+
+        let mvp_location = gl
+            .get_uniform_location(&program, "mvpMatrix")
+            .ok_or("Failed to get uniform location")?;
+
+        let mut camera = OrbitCamera::new(5.0);
+        camera.pitch = -0.4;
+        camera.yaw = 0.5;
+
+        let mut r = Self {
+            gl,
+            program,
+            vao_lin,
+            vao_pts,
+
+            mvp_location,
+
+            camera,
+
+            ik: ik::IK::new(),
+            target: Vec3::new(0.6, 1.0, 0.4),
+            dragging: false,
+
+            clip: None,
+            clip_time: 0.0,
+        };
+
+        r.ik.update(r.target);
+        r.update();
+
+        Ok(r)
+    }
+
+    fn update(&mut self) {
+        let (p, rotations) = self.ik.forward();
+
+        let mut v = vec![];
+        let mut c = vec![];
+        let mut idx = vec![];
+        let mut line = |a: Vec3, b: Vec3, color: [f32; 4]| {
+            let i = (v.len() / 3) as u16;
+            v.extend([a.x, a.y, a.z, b.x, b.y, b.z]);
+            c.extend(color);
+            c.extend(color);
+            idx.extend([i, i + 1]);
+        };
+
+        // ground grid
+        for i in -5..=5 {
+            let x = i as f32 * 0.4;
+            line(
+                Vec3::new(x, 0.0, -2.0),
+                Vec3::new(x, 0.0, 2.0),
+                [0.3, 0.3, 0.3, 1.0],
+            );
+            line(
+                Vec3::new(-2.0, 0.0, x),
+                Vec3::new(2.0, 0.0, x),
+                [0.3, 0.3, 0.3, 1.0],
+            );
+        }
+
+        // bones
+        for w in p.windows(2) {
+            line(w[0], w[1], [1.0, 1.0, 1.0, 1.0]);
+        }
+
+        // joint constraints, drawn in the parent frame
+        for (i, bone) in self.ik.bones.iter().enumerate() {
+            let parent = if i > 0 {
+                rotations[i - 1]
+            } else {
+                UnitQuaternion::identity()
+            };
+            let o = p[i];
+            let r = bone.length * 0.3;
+            match bone.joint {
+                ik::Joint::Free => {}
+                ik::Joint::Hinge { axis, .. } => {
+                    let a = parent * axis * r;
+                    line(o - a, o + a, [0.0, 1.0, 1.0, 1.0]);
+                }
+                ik::Joint::Ball { max_swing, .. } => {
+                    // rim of the swing cone around the parent bone axis
+                    const SEGMENTS: usize = 16;
+                    let (s, co) = max_swing.sin_cos();
+                    let rim = (0..=SEGMENTS)
+                        .map(|k| {
+                            let t = k as f32 / SEGMENTS as f32 * consts::TAU;
+                            o + parent * Vec3::new(t.cos() * s, co, t.sin() * s) * r
+                        })
+                        .collect::<Vec<_>>();
+                    for w in rim.windows(2) {
+                        line(w[0], w[1], [1.0, 0.5, 0.0, 1.0]);
+                    }
+                    line(o, rim[0], [1.0, 0.5, 0.0, 1.0]);
+                }
+            }
+        }
+
+        if let Some(clip) = &self.clip {
+            for (a, b) in clip.segments(self.clip_time) {
+                line(a, b, [1.0, 0.3, 1.0, 1.0]);
+            }
+        }
+        self.vao_lin.send_data(&v, &c, &idx);
+
+        // joints and target
+        let mut v = p.iter().flat_map(|p| [p.x, p.y, p.z]).collect::<Vec<_>>();
+        let mut c = [0.0, 1.0, 0.0, 1.0].repeat(p.len());
+        v.extend([self.target.x, self.target.y, self.target.z]);
+        c.extend(if self.dragging {
+            [1.0, 0.5, 0.0, 1.0]
+        } else {
+            [1.0, 1.0, 0.0, 1.0]
+        });
+        let idx = (0..(v.len() / 3) as u16).collect::<Vec<_>>();
+        self.vao_pts.send_data(&v, &c, &idx);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        let Some(clip) = &self.clip else {
+            return;
+        };
+        self.clip_time = (self.clip_time + dt) % clip.duration().max(1e-3);
+        self.update();
+    }
+
+    fn load_clip(&mut self, src: &str) -> Result<(), String> {
+        let bvh = common::bvh::Bvh::parse(src)?;
+        let clip = clip::Clip::new(bvh)?;
+        let segments = clip.segments(0.0).len();
+        if segments > MAX_CLIP_SEGMENTS {
+            return Err(format!(
+                "the clip has {} bones, at most {} can be shown",
+                segments, MAX_CLIP_SEGMENTS
+            ));
+        }
+        self.clip = Some(clip);
+        self.clip_time = 0.0;
+        self.update();
+        Ok(())
+    }
+
+    fn draw(&self) {
+        self.gl.use_program(Some(&self.program));
+        self.camera.send_mvp_matrix(&self.gl, &self.mvp_location);
+
+        self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        self.gl.clear_depth(1.0);
+        self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+        self.vao_lin.draw_elements(GL::LINES);
+        self.vao_pts.draw_elements(GL::POINTS);
+        self.gl.flush();
+    }
+
+    fn mouse_handler(&mut self, event: web_sys::MouseEvent) {
+        if event.buttons() != 1 {
+            if self.dragging {
+                self.dragging = false;
+                self.update();
+            }
+            return;
+        }
+
+        let p = Vec2::new(
+            (event.offset_x() as f32 / CANVAS_SIZE as f32) * 2. - 1.,
+            -(event.offset_y() as f32 / CANVAS_SIZE as f32) * 2. + 1.,
+        );
+
+        if event.type_() == "mousedown" {
+            const PICK_RADIUS: f32 = 16.0 / CANVAS_SIZE as f32 * 2.0;
+            self.dragging = (self.camera.project(&self.target).xy() - p).norm() < PICK_RADIUS;
+            self.update();
+            return;
+        }
+
+        if !self.dragging {
+            self.camera
+                .move_camera((event.movement_x() as f32, event.movement_y() as f32), 0.0);
+            return;
+        }
+
+        // move the target on the plane facing the camera
+        let (eye, dir) = self.camera.ray(&p);
+        let normal = self.camera.forward();
+        let d = dir.dot(&normal);
+        if d.abs() < 1e-6 {
+            return;
+        }
+        let t = (self.target - eye).dot(&normal) / d;
+        self.target = eye + dir * t;
+
+        self.ik.update(self.target);
+        self.update();
+    }
+
+    fn scroll_handler(&mut self, event: web_sys::WheelEvent) {
+        event.prevent_default();
+        self.camera
+            .move_camera((0.0, 0.0), event.delta_y() as f32 / 100.0);
+    }
+
+    fn set_joints(&mut self, preset: &str) {
+        let hinge = ik::Joint::Hinge {
+            axis: Vec3::new(1.0, 0.0, 0.0),
+            min: -150f32.to_radians(),
+            max: 150f32.to_radians(),
+        };
+        let ball = ik::Joint::Ball {
+            max_swing: 45f32.to_radians(),
+            max_twist: 20f32.to_radians(),
+        };
+
+        for (i, bone) in self.ik.bones.iter_mut().enumerate() {
+            // the root is always free so the chain can face the target
+            bone.joint = match (preset, i) {
+                (_, 0) | ("free", _) => ik::Joint::Free,
+                ("hinge", _) => hinge,
+                ("ball", _) => ball,
+                ("mixed", i) if i % 2 == 1 => hinge,
+                ("mixed", _) => ball,
+                _ => {
+                    return;
+                }
+            };
+            bone.rotation = bone.joint.constrain(&bone.rotation);
+        }
+
+        self.ik.update(self.target);
+        self.update();
+    }
+
+    fn addrmv(&mut self, d: i32) {
+        if d < 0 {
+            self.ik.pop_bone();
+        } else if self.ik.bones.len() < MAX_BONES {
+            let joint = self.ik.bones.last().unwrap().joint;
+            self.ik.add_bone();
+            self.ik.bones.last_mut().unwrap().joint = joint;
+        }
+        self.ik.update(self.target);
+        self.update();
+    }
+}
+
+const CANVAS_SIZE: u32 = 1024;
+
+common::scene_slot!(Scene);
+
+// play a BVH clip next to the IK chain, errors carry the line number
+#[wasm_bindgen]
+pub fn load_bvh(src: &str) -> Result<(), JsValue> {
+    with_scene(|scene| scene.load_clip(src))?.map_err(JsValue::from)
+}
+
+#[wasm_bindgen]
+pub fn clear_bvh() -> Result<(), JsValue> {
+    with_scene(|scene| {
+        scene.clip = None;
+        scene.update();
+    })
+}
+
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    console_error_panic_hook::set_once();
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .get_element_by_id("canvas")
+        .ok_or("canvas not found")?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    let scene = Rc::new(RefCell::new(Scene::new(&canvas)?));
+    set_scene(scene.clone());
+
+    // mousemove handler
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+        scene_.borrow_mut().mouse_handler(event);
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("mousemove", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("mouseup", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("mousedown", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::WheelEvent| {
+        scene_.borrow_mut().scroll_handler(event);
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let btn_add = document
+        .get_element_by_id("btn_add")
+        .ok_or("btn_add not found")?
+        .dyn_into::<HtmlButtonElement>()?;
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |_: web_sys::MouseEvent| {
+        scene_.borrow_mut().addrmv(1);
+    }) as Box<dyn FnMut(_)>);
+    btn_add.add_event_listener_with_callback("click", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let btn_rmv = document
+        .get_element_by_id("btn_rmv")
+        .ok_or("btn_rmv not found")?
+        .dyn_into::<HtmlButtonElement>()?;
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |_: web_sys::MouseEvent| {
+        scene_.borrow_mut().addrmv(-1);
+    }) as Box<dyn FnMut(_)>);
+    btn_rmv.add_event_listener_with_callback("click", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    // input handlers
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        let targ = if let Some(e) = event.target() {
+            e
+        } else {
+            return;
+        };
+        let targ = targ.dyn_into::<HtmlSelectElement>();
+        let targ = if let Ok(e) = targ { e } else { return };
+
+        let targid = targ.name();
+        let val = targ.value();
+
+        if targid == "joints" {
+            scene_.borrow_mut().set_joints(&val);
+        }
+    }) as Box<dyn FnMut(_)>);
+    document.add_event_listener_with_callback("change", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let performance = web_sys::window()
+        .unwrap()
+        .performance()
+        .ok_or("performance not available")?;
+    let mut last_time = performance.now();
+
+    let closure = Rc::new(RefCell::new(None));
+    let closure_ = closure.clone();
+    *closure_.borrow_mut() = Some(Closure::<dyn FnMut() -> Result<i32, JsValue>>::new(
+        move || {
+            let now = performance.now();
+            let dt = (((now - last_time) / 1000.0) as f32).min(0.1);
+            last_time = now;
+
+            scene.borrow_mut().tick(dt);
+            scene.borrow().draw();
+            common::request_animation_frame(closure.borrow().as_ref().unwrap())
+        },
+    ));
+    common::request_animation_frame(closure_.borrow().as_ref().unwrap())?;
+
+    Ok(())
+}
@@ -0,0 +1,456 @@
+// Biovision Hierarchy motion files
+
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    Xposition,
+    Yposition,
+    Zposition,
+    Xrotation,
+    Yrotation,
+    Zrotation,
+}
+
+impl Channel {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "Xposition" => Channel::Xposition,
+            "Yposition" => Channel::Yposition,
+            "Zposition" => Channel::Zposition,
+            "Xrotation" => Channel::Xrotation,
+            "Yrotation" => Channel::Yrotation,
+            "Zrotation" => Channel::Zrotation,
+            _ => return None,
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Channel::Xposition => "Xposition",
+            Channel::Yposition => "Yposition",
+            Channel::Zposition => "Zposition",
+            Channel::Xrotation => "Xrotation",
+            Channel::Yrotation => "Yrotation",
+            Channel::Zrotation => "Zrotation",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Joint {
+    pub name: String,
+    // always precedes this joint in `Bvh::joints`, None for the root
+    pub parent: Option<usize>,
+    pub offset: [f32; 3],
+    pub channels: Vec<Channel>,
+    pub end_site: Option<[f32; 3]>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bvh {
+    // in file order, so parents come first
+    pub joints: Vec<Joint>,
+    // seconds
+    pub frame_time: f32,
+    // one value per channel, in joint order
+    pub frames: Vec<Vec<f32>>,
+}
+
+struct Tokens<'a> {
+    iter: Box<dyn Iterator<Item = (usize, &'a str)> + 'a>,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(src: &'a str) -> Self {
+        // tokens with their line numbers
+        Self {
+            iter: Box::new(
+                src.lines()
+                    .enumerate()
+                    .flat_map(|(i, l)| l.split_whitespace().map(move |t| (i + 1, t))),
+            ),
+        }
+    }
+
+    fn next(&mut self) -> Result<(usize, &'a str), String> {
+        self.iter
+            .next()
+            .ok_or_else(|| "unexpected end of file".to_string())
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        let (line, t) = self.next()?;
+        if t != s {
+            return Err(format!("line {}: expected `{}`, found `{}`", line, s, t));
+        }
+        Ok(())
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        let (line, t) = self.next()?;
+        t.parse()
+            .map_err(|_| format!("line {}: expected a number, found `{}`", line, t))
+    }
+
+    fn vec3(&mut self) -> Result<[f32; 3], String> {
+        Ok([self.number()?, self.number()?, self.number()?])
+    }
+}
+
+impl Bvh {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut tokens = Tokens::new(src);
+        let mut joints = vec![];
+
+        tokens.expect("HIERARCHY")?;
+        tokens.expect("ROOT")?;
+        parse_joint(&mut tokens, &mut joints, None, 0)?;
+
+        tokens.expect("MOTION")?;
+        tokens.expect("Frames:")?;
+        let count: usize = tokens.number()?;
+        tokens.expect("Frame")?;
+        tokens.expect("Time:")?;
+        let frame_time = tokens.number()?;
+
+        let channels = joints.iter().map(|j| j.channels.len()).sum::<usize>();
+        if channels == 0 && count > 0 {
+            return Err(format!("{} frames but no channels", count));
+        }
+        // the count comes from the file, every frame takes at least one token of the source
+        let mut frames = Vec::with_capacity(count.min(src.len()));
+        for _ in 0..count {
+            let frame = (0..channels)
+                .map(|_| tokens.number())
+                .collect::<Result<Vec<f32>, _>>()?;
+            frames.push(frame);
+        }
+        if let Ok((line, t)) = tokens.next() {
+            return Err(format!(
+                "line {}: unexpected `{}` after {} frames",
+                line, t, count
+            ));
+        }
+
+        Ok(Self {
+            joints,
+            frame_time,
+            frames,
+        })
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.joints.iter().map(|j| j.channels.len()).sum()
+    }
+
+    // index of the first channel of each joint within a frame
+    pub fn channel_offsets(&self) -> Vec<usize> {
+        self.joints
+            .iter()
+            .scan(0, |acc, j| {
+                let r = *acc;
+                *acc += j.channels.len();
+                Some(r)
+            })
+            .collect()
+    }
+
+    // joints in the order they appear in a file, depth first from the root
+    fn file_order(&self) -> Vec<usize> {
+        fn visit(bvh: &Bvh, i: usize, order: &mut Vec<usize>) {
+            order.push(i);
+            for (j, child) in bvh.joints.iter().enumerate() {
+                if child.parent == Some(i) {
+                    visit(bvh, j, order);
+                }
+            }
+        }
+        let mut order = vec![];
+        if !self.joints.is_empty() {
+            visit(self, 0, &mut order);
+        }
+        order
+    }
+
+    // joints may be stored in any order with parents first,
+    // the frames are rearranged to match the written hierarchy
+    pub fn write(&self) -> String {
+        let mut s = String::from("HIERARCHY\n");
+        if !self.joints.is_empty() {
+            self.write_joint(&mut s, 0, 0);
+        }
+        writeln!(s, "MOTION").unwrap();
+        writeln!(s, "Frames: {}", self.frames.len()).unwrap();
+        writeln!(s, "Frame Time: {:.6}", self.frame_time).unwrap();
+        let order = self.file_order();
+        let offsets = self.channel_offsets();
+        for frame in &self.frames {
+            let line = order
+                .iter()
+                .flat_map(|&j| &frame[offsets[j]..offsets[j] + self.joints[j].channels.len()])
+                .map(|v| format!("{:.6}", v))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(s, "{}", line).unwrap();
+        }
+        s
+    }
+
+    fn write_joint(&self, s: &mut String, i: usize, depth: usize) {
+        let indent = "\t".repeat(depth);
+        let joint = &self.joints[i];
+        let kind = if joint.parent.is_none() {
+            "ROOT"
+        } else {
+            "JOINT"
+        };
+        let [x, y, z] = joint.offset;
+        writeln!(s, "{}{} {}", indent, kind, joint.name).unwrap();
+        writeln!(s, "{}{{", indent).unwrap();
+        writeln!(s, "{}\tOFFSET {:.6} {:.6} {:.6}", indent, x, y, z).unwrap();
+        let channels = joint
+            .channels
+            .iter()
+            .map(|c| c.name())
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(
+            s,
+            "{}\tCHANNELS {} {}",
+            indent,
+            joint.channels.len(),
+            channels
+        )
+        .unwrap();
+        for (j, child) in self.joints.iter().enumerate() {
+            if child.parent == Some(i) {
+                self.write_joint(s, j, depth + 1);
+            }
+        }
+        if let Some([x, y, z]) = joint.end_site {
+            writeln!(s, "{}\tEnd Site", indent).unwrap();
+            writeln!(s, "{}\t{{", indent).unwrap();
+            writeln!(s, "{}\t\tOFFSET {:.6} {:.6} {:.6}", indent, x, y, z).unwrap();
+            writeln!(s, "{}\t}}", indent).unwrap();
+        }
+        writeln!(s, "{}}}", indent).unwrap();
+    }
+}
+
+// joints nest by recursion, deeper files are rejected rather than overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+// the name and body of a ROOT or JOINT, the keyword is already consumed
+fn parse_joint(
+    tokens: &mut Tokens,
+    joints: &mut Vec<Joint>,
+    parent: Option<usize>,
+    depth: usize,
+) -> Result<(), String> {
+    let (_, name) = tokens.next()?;
+    tokens.expect("{")?;
+    tokens.expect("OFFSET")?;
+    let offset = tokens.vec3()?;
+
+    tokens.expect("CHANNELS")?;
+    let n: usize = tokens.number()?;
+    let channels = (0..n)
+        .map(|_| {
+            let (line, t) = tokens.next()?;
+            Channel::parse(t).ok_or_else(|| format!("line {}: unknown channel `{}`", line, t))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let index = joints.len();
+    joints.push(Joint {
+        name: name.to_string(),
+        parent,
+        offset,
+        channels,
+        end_site: None,
+    });
+
+    loop {
+        let (line, t) = tokens.next()?;
+        match t {
+            "JOINT" if depth + 1 >= MAX_DEPTH => {
+                return Err(format!(
+                    "line {}: joints nested deeper than {}",
+                    line, MAX_DEPTH
+                ))
+            }
+            "JOINT" => parse_joint(tokens, joints, Some(index), depth + 1)?,
+            "End" => {
+                tokens.expect("Site")?;
+                tokens.expect("{")?;
+                tokens.expect("OFFSET")?;
+                joints[index].end_site = Some(tokens.vec3()?);
+                tokens.expect("}")?;
+            }
+            "}" => return Ok(()),
+            _ => {
+                return Err(format!(
+                    "line {}: expected `JOINT`, `End Site` or `}}`, found `{}`",
+                    line, t
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str = include_str!("../samples/simple.bvh");
+    const BRANCHED: &str = include_str!("../samples/branched.bvh");
+
+    #[test]
+    fn parse_simple() {
+        let bvh = Bvh::parse(SIMPLE).unwrap();
+        assert_eq!(bvh.joints.len(), 3);
+        assert_eq!(bvh.joints[0].name, "Hips");
+        assert_eq!(bvh.joints[0].parent, None);
+        assert_eq!(bvh.joints[2].parent, Some(1));
+        assert_eq!(bvh.joints[1].offset, [0.0, 10.0, 0.0]);
+        assert_eq!(bvh.joints[2].end_site, Some([0.0, 5.0, 0.0]));
+        assert_eq!(bvh.joints[0].channels.len(), 6);
+        assert_eq!(
+            bvh.joints[1].channels,
+            [Channel::Zrotation, Channel::Xrotation, Channel::Yrotation]
+        );
+        assert_eq!(bvh.channel_count(), 12);
+        assert_eq!(bvh.channel_offsets(), [0, 6, 9]);
+        assert_eq!(bvh.frames.len(), 3);
+        assert!((bvh.frame_time - 0.033333).abs() < 1e-6);
+        assert_eq!(bvh.frames[1][6], 45.0);
+    }
+
+    #[test]
+    fn parse_branched() {
+        let bvh = Bvh::parse(BRANCHED).unwrap();
+        let names = bvh
+            .joints
+            .iter()
+            .map(|j| j.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "Hips",
+                "Spine",
+                "Head",
+                "LeftLeg",
+                "LeftFoot",
+                "RightLeg",
+                "RightFoot"
+            ]
+        );
+        let parents = bvh.joints.iter().map(|j| j.parent).collect::<Vec<_>>();
+        assert_eq!(
+            parents,
+            [None, Some(0), Some(1), Some(0), Some(3), Some(0), Some(5)]
+        );
+        let ends = bvh.joints.iter().filter(|j| j.end_site.is_some()).count();
+        assert_eq!(ends, 3);
+        assert_eq!(bvh.frames.len(), 2);
+        assert!(bvh.frames.iter().all(|f| f.len() == bvh.channel_count()));
+    }
+
+    #[test]
+    fn write_round_trip() {
+        for src in [SIMPLE, BRANCHED] {
+            let bvh = Bvh::parse(src).unwrap();
+            let written = bvh.write();
+            assert_eq!(Bvh::parse(&written).unwrap(), bvh);
+        }
+    }
+
+    #[test]
+    fn write_reorders_channels() {
+        // children of the root stored after a grandchild
+        let joint = |name: &str, parent| Joint {
+            name: name.to_string(),
+            parent,
+            offset: [0.0, 1.0, 0.0],
+            channels: vec![Channel::Zrotation],
+            end_site: None,
+        };
+        let bvh = Bvh {
+            joints: vec![
+                joint("A", None),
+                joint("B", Some(0)),
+                joint("C", Some(0)),
+                joint("D", Some(1)),
+            ],
+            frame_time: 0.5,
+            frames: vec![vec![0.0, 1.0, 2.0, 3.0]],
+        };
+        let parsed = Bvh::parse(&bvh.write()).unwrap();
+        let names = parsed
+            .joints
+            .iter()
+            .map(|j| j.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["A", "B", "D", "C"]);
+        assert_eq!(parsed.joints[2].parent, Some(1));
+        assert_eq!(parsed.frames, [[0.0, 1.0, 3.0, 2.0]]);
+    }
+
+    #[test]
+    fn reject_wrong_frame_size() {
+        let src = SIMPLE.replace("0.0 0.0 0.0 0.0 0.0 0.0 0.0 0.0 0.0 0.0 0.0 0.0\n", "0.0\n");
+        assert!(Bvh::parse(&src).is_err());
+    }
+
+    #[test]
+    fn reject_truncated_frames() {
+        let src = SIMPLE.replace("Frames: 3", "Frames: 4");
+        assert_ne!(src, SIMPLE);
+        assert!(Bvh::parse(&src).is_err());
+    }
+
+    #[test]
+    fn reject_oversized_frame_count() {
+        let src = SIMPLE.replace("Frames: 3", "Frames: 4000000000");
+        assert_ne!(src, SIMPLE);
+        assert!(Bvh::parse(&src).is_err());
+    }
+
+    #[test]
+    fn report_error_line() {
+        let src = SIMPLE.replace("Xrotation", "Wrotation");
+        let err = Bvh::parse(&src).unwrap_err();
+        assert!(err.starts_with("line 5:"), "{}", err);
+    }
+
+    #[test]
+    fn reject_unclosed_joint() {
+        let end = BRANCHED.find("MOTION").unwrap();
+        let src = BRANCHED[..end].trim_end().trim_end_matches('}');
+        assert!(Bvh::parse(src).is_err());
+    }
+
+    #[test]
+    fn reject_deep_nesting() {
+        // a chain of `n` joints, each header four lines below the previous one
+        let chain = |n: usize| {
+            let mut src = String::from("HIERARCHY\nROOT J0\n{\nOFFSET 0 0 0\nCHANNELS 0\n");
+            for i in 1..n {
+                src += &format!("JOINT J{}\n{{\nOFFSET 0 1 0\nCHANNELS 0\n", i);
+            }
+            src + &"}\n".repeat(n) + "MOTION\nFrames: 0\nFrame Time: 0.1\n"
+        };
+        assert_eq!(
+            Bvh::parse(&chain(MAX_DEPTH)).unwrap().joints.len(),
+            MAX_DEPTH
+        );
+        let err = Bvh::parse(&chain(100_000)).unwrap_err();
+        assert!(
+            err.starts_with(&format!("line {}:", 2 + 4 * MAX_DEPTH)),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,91 @@
+use nalgebra_glm as glm;
+use std::f32::consts;
+use web_sys::{WebGl2RenderingContext as GL, *};
+
+// camera orbiting around the origin
+pub struct OrbitCamera {
+    pub dist: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+}
+
+impl OrbitCamera {
+    pub fn new(dist: f32) -> Self {
+        Self {
+            dist,
+            pitch: 0.0,
+            yaw: 0.0,
+        }
+    }
+
+    pub fn move_camera(&mut self, pan: (f32, f32), zoom: f32) {
+        self.yaw -= pan.0 * 0.01;
+        self.pitch -= pan.1 * 0.01;
+
+        self.pitch = self
+            .pitch
+            .clamp(-consts::FRAC_PI_2 + 1e-6, consts::FRAC_PI_2 - 1e-6);
+
+        self.dist *= 2.0f32.powf(zoom);
+        self.dist = self.dist.clamp(1., 100.);
+    }
+
+    pub fn eye(&self) -> glm::Vec3 {
+        glm::quat_rotate_vec3(
+            &(glm::quat_angle_axis(self.yaw, &glm::Vec3::new(0.0, 1.0, 0.0))
+                * glm::quat_angle_axis(self.pitch, &glm::Vec3::new(1.0, 0.0, 0.0))),
+            &glm::Vec3::new(0.0, 0.0, self.dist),
+        )
+    }
+
+    pub fn view_matrix(&self) -> glm::Mat4 {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+        let up = glm::Vec3::new(0.0, 1.0, 0.0);
+        glm::look_at(&self.eye(), &center, &up)
+    }
+
+    pub fn projection_matrix(&self) -> glm::Mat4 {
+        let aspect = 1.0;
+        let fovy = 45.0 * consts::PI / 180.0;
+        let near = 0.1;
+        let far = 100.0;
+        glm::perspective(aspect, fovy, near, far)
+    }
+
+    pub fn mvp_matrix(&self) -> glm::Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    // world position to normalized device coordinates
+    pub fn project(&self, p: &glm::Vec3) -> glm::Vec3 {
+        let v = self.mvp_matrix() * glm::Vec4::new(p.x, p.y, p.z, 1.0);
+        v.xyz() / v.w
+    }
+
+    // ray (origin, unit direction) through a point given in normalized device coordinates
+    pub fn ray(&self, ndc: &glm::Vec2) -> (glm::Vec3, glm::Vec3) {
+        let inv = glm::inverse(&self.mvp_matrix());
+        let far = inv * glm::Vec4::new(ndc.x, ndc.y, 1.0, 1.0);
+        let far = far.xyz() / far.w;
+        let eye = self.eye();
+        (eye, (far - eye).normalize())
+    }
+
+    // unit vector from the eye toward the orbit center
+    pub fn forward(&self) -> glm::Vec3 {
+        -self.eye().normalize()
+    }
+
+    pub fn send_mvp_matrix(&self, gl: &GL, location: &WebGlUniformLocation) {
+        let mvp_arrays: [[f32; 4]; 4] = self.mvp_matrix().into();
+        let mvp_matrices = mvp_arrays.iter().flat_map(|a| *a).collect::<Vec<_>>();
+
+        gl.uniform_matrix4fv_with_f32_array_and_src_offset_and_src_length(
+            Some(location),
+            false,
+            &mvp_matrices,
+            0,
+            0,
+        );
+    }
+}
@@ -0,0 +1,177 @@
+// curves through 2D or 3D control points
+use nalgebra_glm::TVec;
+
+#[derive(Clone, Copy)]
+pub enum CatmullRomParmType {
+    Uniform,
+    ChordLength,
+    Centripetal,
+}
+
+#[derive(Clone, Copy)]
+pub enum CurveType {
+    Bezier,
+    CatmullRom(CatmullRomParmType),
+}
+
+fn make_bezier_normal<const D: usize>(points: &[TVec<f32, D>], n: usize) -> Vec<TVec<f32, D>> {
+    let m = points.len();
+    (0..n + 1)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let mut v = points.to_vec();
+            for j in (1..m).rev() {
+                for k in 0..j {
+                    v[k] = v[k] * (1. - t) + v[k + 1] * t;
+                }
+            }
+            v[0]
+        })
+        .collect()
+}
+
+// a point and its knot
+type Knot<const D: usize> = (TVec<f32, D>, f32);
+
+fn lerp<const D: usize>(a: Knot<D>, b: Knot<D>, z: f32) -> TVec<f32, D> {
+    let t = (z - a.1) / (b.1 - a.1);
+    a.0 * (1. - t) + b.0 * t
+}
+
+// the end segments, over three points
+fn quadratic<const D: usize>(a: Knot<D>, b: Knot<D>, c: Knot<D>, z: f32) -> TVec<f32, D> {
+    let p0 = (lerp(a, b, z), a.1);
+    let p1 = (lerp(b, c, z), c.1);
+    lerp(p0, p1, z)
+}
+
+fn cubic<const D: usize>(a: Knot<D>, b: Knot<D>, c: Knot<D>, d: Knot<D>, z: f32) -> TVec<f32, D> {
+    let p0 = (quadratic(a, b, c, z), b.1);
+    let p1 = (quadratic(b, c, d, z), c.1);
+    lerp(p0, p1, z)
+}
+
+fn make_catmull_rom<const D: usize>(
+    points: &[TVec<f32, D>],
+    n: usize,
+    curvetype: CatmullRomParmType,
+) -> Vec<TVec<f32, D>> {
+    let m = points.len();
+    if m < 2 {
+        return points.iter().flat_map(|p| vec![*p; n + 1]).collect();
+    }
+
+    let mut segments = points.iter().map(|w| (*w, 0.0)).collect::<Vec<_>>();
+
+    for i in 1..m {
+        let d = match curvetype {
+            CatmullRomParmType::Uniform => 1.0,
+            CatmullRomParmType::ChordLength => (points[i] - points[i - 1]).norm(),
+            CatmullRomParmType::Centripetal => (points[i] - points[i - 1]).norm().sqrt(),
+        };
+        segments[i].1 = segments[i - 1].1 + d;
+    }
+
+    let sum_len = segments[m - 1].1;
+
+    let mut idx = 0;
+    (0..n + 1)
+        .map(|i| {
+            let t = i as f32 / n as f32 * sum_len;
+            while idx + 2 < m && t > segments[idx + 1].1 {
+                idx += 1;
+            }
+
+            // segment idx..idx+1, with the neighbors that exist on either side
+            let (a, b) = (segments[idx], segments[idx + 1]);
+            let prev = idx.checked_sub(1).map(|i| segments[i]);
+            match (prev, segments.get(idx + 2)) {
+                (Some(p), Some(&q)) => cubic(p, a, b, q, t),
+                (Some(p), None) => quadratic(p, a, b, t),
+                (None, Some(&q)) => quadratic(a, b, q, t),
+                (None, None) => lerp(a, b, t),
+            }
+        })
+        .collect()
+}
+
+pub fn make_curve<const D: usize>(
+    points: &[TVec<f32, D>],
+    n: usize,
+    curvetype: CurveType,
+) -> Vec<TVec<f32, D>> {
+    match curvetype {
+        CurveType::Bezier if points.is_empty() => vec![],
+        CurveType::Bezier => make_bezier_normal(points, n),
+        CurveType::CatmullRom(t) => make_catmull_rom(points, n, t),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{vec2, vec3};
+
+    const TYPES: [CurveType; 4] = [
+        CurveType::Bezier,
+        CurveType::CatmullRom(CatmullRomParmType::Uniform),
+        CurveType::CatmullRom(CatmullRomParmType::ChordLength),
+        CurveType::CatmullRom(CatmullRomParmType::Centripetal),
+    ];
+
+    #[test]
+    fn ends_at_the_control_points() {
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(1.0, 2.0),
+            vec2(3.0, 1.0),
+            vec2(4.0, 3.0),
+        ];
+        for t in TYPES {
+            let curve = make_curve(&points, 20, t);
+            assert_eq!(curve.len(), 21);
+            assert!((curve[0] - points[0]).norm() < 1e-5);
+            assert!((curve[20] - points[3]).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_the_control_points() {
+        // uniform knots, so that every fifth sample is a control point
+        let points = [
+            vec2(0.0, 0.0),
+            vec2(1.0, 2.0),
+            vec2(3.0, 1.0),
+            vec2(4.0, 3.0),
+        ];
+        let curve = make_curve(&points, 15, TYPES[1]);
+        for (i, p) in points.iter().enumerate() {
+            assert!((curve[i * 5] - p).norm() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn dimensions_agree() {
+        let flat = [vec2(0.0, 0.0), vec2(1.0, 2.0), vec2(3.0, 1.0)];
+        let lifted = flat.map(|p| vec3(p.x, p.y, 0.0));
+        for t in TYPES {
+            for (a, b) in make_curve(&flat, 10, t)
+                .iter()
+                .zip(make_curve(&lifted, 10, t))
+            {
+                assert!((vec3(a.x, a.y, 0.0) - b).norm() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn few_points() {
+        for t in TYPES {
+            assert!(make_curve::<2>(&[], 10, t).is_empty());
+            let one = make_curve(&[vec2(1.0, 2.0)], 10, t);
+            assert!(one.iter().all(|p| *p == vec2(1.0, 2.0)));
+            let two = make_curve(&[vec2(0.0, 0.0), vec2(2.0, 0.0)], 2, t);
+            assert!((two[1] - vec2(1.0, 0.0)).norm() < 1e-6);
+        }
+    }
+}
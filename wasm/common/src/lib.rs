@@ -3,6 +3,9 @@ extern crate nalgebra_glm as glm;
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{WebGl2RenderingContext as GL, *};
 
+pub mod bvh;
+pub mod camera;
+pub mod curves;
 pub mod vao;
 
 pub fn create_program(
@@ -63,3 +66,28 @@ pub fn request_animation_frame(
     let window = web_sys::window().unwrap();
     window.request_animation_frame(closure.as_ref().unchecked_ref())
 }
+
+// the scene of a page for the functions exported to JavaScript. defines `set_scene`,
+// called by `start`, and `with_scene`, which fails until then
+#[macro_export]
+macro_rules! scene_slot {
+    ($scene:ty) => {
+        thread_local! {
+            static SCENE: std::cell::RefCell<Option<std::rc::Rc<std::cell::RefCell<$scene>>>> =
+                const { std::cell::RefCell::new(None) };
+        }
+
+        fn set_scene(scene: std::rc::Rc<std::cell::RefCell<$scene>>) {
+            SCENE.with(|s| *s.borrow_mut() = Some(scene));
+        }
+
+        fn with_scene<T>(f: impl FnOnce(&mut $scene) -> T) -> Result<T, wasm_bindgen::JsValue> {
+            SCENE.with(|s| {
+                let s = s.borrow();
+                let scene = s.as_ref().ok_or("scene not initialized")?;
+                let r = f(&mut scene.borrow_mut());
+                Ok(r)
+            })
+        }
+    };
+}
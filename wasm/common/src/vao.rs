@@ -5,12 +5,14 @@ use web_sys::{WebGl2RenderingContext as GL, *};
 /*
 vertex vbo binds to attribute 0
 color vbo binds to attribute 1
+normal vbo (if any) binds to attribute 2
 */
 pub struct MyVAO {
     gl: Rc<GL>,
     vao: WebGlVertexArrayObject,
     vbo_vtx: WebGlBuffer,
     vbo_col: WebGlBuffer,
+    vbo_nrm: Option<WebGlBuffer>,
     ibo: WebGlBuffer,
     ibo_len: i32,
 }
@@ -47,11 +49,27 @@ impl MyVAO {
             vao,
             vbo_vtx,
             vbo_col,
+            vbo_nrm: None,
             ibo,
             ibo_len: 0,
         })
     }
 
+    pub fn new_with_normal(gl: Rc<GL>, vbo_size: usize, ibo_size: usize) -> Result<Self, String> {
+        let mut r = Self::new(gl.clone(), vbo_size, ibo_size)?;
+
+        gl.bind_vertex_array(Some(&r.vao));
+        gl.enable_vertex_attrib_array(2);
+
+        let vbo_nrm = gl.create_buffer().ok_or("Failed to create buffer")?;
+        gl.bind_buffer(GL::ARRAY_BUFFER, Some(&vbo_nrm));
+        gl.buffer_data_with_i32(GL::ARRAY_BUFFER, 4 * vbo_size as i32 * 3, GL::DYNAMIC_DRAW);
+        gl.vertex_attrib_pointer_with_i32(2, 3, GL::FLOAT, false, 0, 0);
+
+        r.vbo_nrm = Some(vbo_nrm);
+        Ok(r)
+    }
+
     pub fn send_data(&mut self, vtx_data: &[f32], col_data: &[f32], idx_data: &[u16]) {
         self.gl.bind_vertex_array(Some(&self.vao));
 
@@ -74,6 +92,17 @@ impl MyVAO {
         self.ibo_len = idx_data.len() as i32;
     }
 
+    // only valid for VAOs created with `new_with_normal`
+    pub fn send_normal_data(&self, nrm_data: &[f32]) {
+        let vbo_nrm = self.vbo_nrm.as_ref().expect("VAO has no normal buffer");
+        self.gl.bind_vertex_array(Some(&self.vao));
+
+        self.gl.bind_buffer(GL::ARRAY_BUFFER, Some(vbo_nrm));
+        let view = unsafe { js_sys::Float32Array::view(nrm_data) };
+        self.gl
+            .buffer_sub_data_with_i32_and_array_buffer_view(GL::ARRAY_BUFFER, 0, &view);
+    }
+
     pub fn draw_elements(&self, mode: u32) {
         self.gl.bind_vertex_array(Some(&self.vao));
         self.gl
@@ -0,0 +1,93 @@
+use nalgebra_glm::Vec2;
+
+#[derive(Clone)]
+pub enum SpeedProfile {
+    Constant,
+    EaseInOut,
+    // relative speed sampled at uniform time steps, linearly interpolated
+    Custom(Vec<f32>),
+}
+
+impl SpeedProfile {
+    // parse comma separated speed samples like "1, 3, 1"
+    pub fn parse_custom(s: &str) -> Option<Self> {
+        let v = s
+            .split(',')
+            .map(|w| w.trim().parse::<f32>().ok().filter(|v| *v >= 0.0))
+            .collect::<Option<Vec<_>>>()?;
+        if v.is_empty() || v.iter().sum::<f32>() <= 0.0 {
+            return None;
+        }
+        Some(Self::Custom(v))
+    }
+
+    // fraction of the total length travelled at normalized time t (0..=1)
+    pub fn progress(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Constant => t,
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Custom(v) => {
+                if v.len() == 1 {
+                    return t;
+                }
+                // integrate the piecewise linear speed curve
+                let m = v.len() - 1;
+                let seg = |i: usize| (v[i] + v[i + 1]) * 0.5;
+                let total = (0..m).map(seg).sum::<f32>();
+
+                let x = t * m as f32;
+                let i = (x as usize).min(m - 1);
+                let u = x - i as f32;
+                let part = v[i] * u + (v[i + 1] - v[i]) * u * u * 0.5;
+                ((0..i).map(seg).sum::<f32>() + part) / total
+            }
+        }
+    }
+}
+
+// polyline with cumulative arc length, for constant speed sampling
+pub struct ArcLengthPath {
+    points: Vec<Vec2>,
+    lengths: Vec<f32>,
+}
+
+impl ArcLengthPath {
+    pub fn new(points: Vec<Vec2>) -> Self {
+        let mut lengths = vec![0.0];
+        for w in points.windows(2) {
+            lengths.push(lengths.last().unwrap() + (w[1] - w[0]).norm());
+        }
+        Self { points, lengths }
+    }
+
+    pub fn length(&self) -> f32 {
+        *self.lengths.last().unwrap()
+    }
+
+    // position and unit tangent at the fraction s (0..=1) of the arc length
+    pub fn sample(&self, s: f32) -> (Vec2, Vec2) {
+        let n = self.points.len();
+        if n < 2 || self.length() <= 0.0 {
+            return (self.points[0], Vec2::new(1.0, 0.0));
+        }
+
+        let l = s.clamp(0.0, 1.0) * self.length();
+        let i = self.lengths.partition_point(|&v| v <= l).clamp(1, n - 1) - 1;
+
+        let d = self.points[i + 1] - self.points[i];
+        let seg = self.lengths[i + 1] - self.lengths[i];
+        let t = if seg > 0.0 {
+            (l - self.lengths[i]) / seg
+        } else {
+            0.0
+        };
+
+        let tangent = if d.norm() > 0.0 {
+            d.normalize()
+        } else {
+            Vec2::new(1.0, 0.0)
+        };
+        (self.points[i] + d * t, tangent)
+    }
+}
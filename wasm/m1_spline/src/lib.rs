@@ -1,20 +1,36 @@
-use common::vao::MyVAO;
+use common::{camera::OrbitCamera, curves, vao::MyVAO};
 use glm::Vec2;
 use nalgebra_glm as glm;
 use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{WebGl2RenderingContext as GL, *};
 
-mod curves;
+mod anim;
+mod revolve;
+
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Edit,
+    Revolve,
+}
 
 struct Scene {
     gl: Rc<GL>,
     program: WebGlProgram,
+    program_lit: WebGlProgram,
 
     vao_lin: MyVAO,
     vao_tri: MyVAO,
+    vao_marker: MyVAO,
+    vao_mesh: MyVAO,
 
     mvp_location: WebGlUniformLocation,
+    mvp_location_lit: WebGlUniformLocation,
+
+    view: View,
+    camera: OrbitCamera,
+    revolve_angle: f32,
+    revolve_segments: usize,
 
     points: Vec<Vec2>,
 
@@ -22,9 +38,20 @@ struct Scene {
     curvetype: curves::CurveType,
 
     dragging: Option<usize>,
+
+    path: anim::ArcLengthPath,
+    playing: bool,
+    anim_time: f32,
+    anim_duration: f32,
+    speed_profile: anim::SpeedProfile,
 }
 
 const MAX_POINTS: usize = 1024;
+const PATH_SAMPLES: usize = 512;
+const MAX_SEGMENTS: usize = 64;
+const MAX_PROFILE: usize = 128;
+// a partial sweep has one more column than segments
+const MAX_MESH_POINTS: usize = (MAX_PROFILE + 1) * (MAX_SEGMENTS + 1);
 impl Scene {
     fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
         canvas.set_width(CANVAS_SIZE);
@@ -40,6 +67,11 @@ impl Scene {
             include_str!("shader/vertex.glsl"),
             include_str!("shader/fragment.glsl"),
         )?;
+        let program_lit = common::create_program(
+            &gl,
+            include_str!("shader/vertex_lit.glsl"),
+            include_str!("shader/fragment_lit.glsl"),
+        )?;
 
         gl.enable(GL::DEPTH_TEST);
         gl.depth_func(GL::LEQUAL);
@@ -47,18 +79,32 @@ impl Scene {
 
         let vao_lin = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 2)?;
         let vao_tri = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 3)?;
+        let vao_marker = MyVAO::new(gl.clone(), 3, 3)?;
+        let vao_mesh = MyVAO::new_with_normal(gl.clone(), MAX_MESH_POINTS, MAX_MESH_POINTS * 6)?;
 
         let mvp_location = gl
             .get_uniform_location(&program, "mvpMatrix")
             .ok_or("Failed to get uniform location")?;
+        let mvp_location_lit = gl
+            .get_uniform_location(&program_lit, "mvpMatrix")
+            .ok_or("Failed to get uniform location")?;
 
         let mut r = Self {
             gl,
             program,
+            program_lit,
             vao_lin,
             vao_tri,
+            vao_marker,
+            vao_mesh,
 
             mvp_location,
+            mvp_location_lit,
+
+            view: View::Edit,
+            camera: OrbitCamera::new(3.0),
+            revolve_angle: std::f32::consts::TAU,
+            revolve_segments: 32,
 
             points: vec![
                 Vec2::new(-0.4, -0.5),
@@ -71,6 +117,12 @@ impl Scene {
             curvetype: curves::CurveType::Bezier,
 
             dragging: None,
+
+            path: anim::ArcLengthPath::new(vec![Vec2::zeros()]),
+            playing: false,
+            anim_time: 0.0,
+            anim_duration: 3.0,
+            speed_profile: anim::SpeedProfile::Constant,
         };
 
         r.update();
@@ -142,18 +194,111 @@ impl Scene {
         let idx = (0..((spline.len() + self.points.len()) * 3) as u16).collect::<Vec<_>>();
 
         self.vao_tri.send_data(&v, &c, &idx);
+
+        // dense resampling so that the arc length is accurate regardless of splitnum
+        self.path = anim::ArcLengthPath::new(curves::make_curve(
+            &self.points,
+            PATH_SAMPLES,
+            self.curvetype,
+        ));
+        self.update_marker();
+        self.update_mesh();
     }
 
-    fn draw(&self) {
-        self.gl.use_program(Some(&self.program));
-        send_mvp_matrix(&self.gl, &self.mvp_location);
+    fn update_mesh(&mut self) {
+        if self.view != View::Revolve {
+            return;
+        }
+
+        let profile =
+            curves::make_curve(&self.points, self.splitnum.min(MAX_PROFILE), self.curvetype);
+        let mesh = revolve::revolve(&profile, self.revolve_angle, self.revolve_segments);
 
+        let v = mesh
+            .vertices
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let n = mesh
+            .normals
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let c = [0.8, 0.8, 1.0, 1.0].repeat(mesh.vertices.len());
+        self.vao_mesh.send_data(&v, &c, &mesh.indices);
+        self.vao_mesh.send_normal_data(&n);
+    }
+
+    fn update_marker(&mut self) {
+        if !self.playing {
+            self.vao_marker.send_data(&[], &[], &[]);
+            return;
+        }
+
+        let t = self.anim_time / self.anim_duration;
+        let (p, d) = self.path.sample(self.speed_profile.progress(t));
+        let n = Vec2::new(-d.y, d.x);
+
+        const MARKER_SIZE: f32 = 0.03;
+        let v0 = p + d * MARKER_SIZE;
+        let v1 = p - d * MARKER_SIZE + n * MARKER_SIZE * 0.6;
+        let v2 = p - d * MARKER_SIZE - n * MARKER_SIZE * 0.6;
+
+        let v = [v0.x, v0.y, 0.0, v1.x, v1.y, 0.0, v2.x, v2.y, 0.0];
+        let c = [1.0, 1.0, 0.0, 1.0].repeat(3);
+        self.vao_marker.send_data(&v, &c, &[0, 1, 2]);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.anim_time = (self.anim_time + dt) % self.anim_duration;
+        self.update_marker();
+    }
+
+    fn set_playing(&mut self, playing: bool) {
+        self.playing = playing;
+        self.anim_time = 0.0;
+        self.update_marker();
+    }
+
+    fn set_duration(&mut self, d: f32) {
+        if d.is_nan() || d <= 0.0 {
+            return;
+        }
+        self.anim_time *= d / self.anim_duration;
+        self.anim_duration = d;
+    }
+
+    fn draw(&self) {
         self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
         self.gl.clear_depth(1.0);
         self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 
+        if self.view == View::Revolve {
+            // the revolved surface is shaded on both sides
+            self.gl.disable(GL::CULL_FACE);
+            self.gl.use_program(Some(&self.program_lit));
+            self.camera
+                .send_mvp_matrix(&self.gl, &self.mvp_location_lit);
+            self.vao_mesh.draw_elements(GL::TRIANGLES);
+            self.gl.enable(GL::CULL_FACE);
+
+            self.gl.use_program(Some(&self.program));
+            self.camera.send_mvp_matrix(&self.gl, &self.mvp_location);
+            self.vao_lin.draw_elements(GL::LINES);
+            self.vao_marker.draw_elements(GL::TRIANGLES);
+            self.gl.flush();
+            return;
+        }
+
+        self.gl.use_program(Some(&self.program));
+        send_mvp_matrix(&self.gl, &self.mvp_location);
+
         self.vao_lin.draw_elements(GL::LINES);
         self.vao_tri.draw_elements(GL::TRIANGLES);
+        self.vao_marker.draw_elements(GL::TRIANGLES);
         self.gl.flush();
     }
 
@@ -163,6 +308,12 @@ impl Scene {
             return;
         }
 
+        if self.view == View::Revolve {
+            self.camera
+                .move_camera((event.movement_x() as f32, event.movement_y() as f32), 0.0);
+            return;
+        }
+
         let p = Vec2::new(
             (event.offset_x() as f32 / CANVAS_SIZE as f32) * 2. - 1.,
             -(event.offset_y() as f32 / CANVAS_SIZE as f32) * 2. + 1.,
@@ -189,6 +340,39 @@ impl Scene {
         self.update();
     }
 
+    fn scroll_handler(&mut self, event: web_sys::WheelEvent) {
+        if self.view != View::Revolve {
+            return;
+        }
+        event.prevent_default();
+        self.camera
+            .move_camera((0.0, 0.0), event.delta_y() as f32 / 100.0);
+    }
+
+    fn set_view(&mut self, view: View) {
+        self.view = view;
+        self.dragging = None;
+        self.update_mesh();
+    }
+
+    fn set_revolve_angle(&mut self, deg: f32) {
+        if deg.is_nan() || deg <= 0.0 || deg > 360.0 {
+            return;
+        }
+
+        self.revolve_angle = deg.to_radians();
+        self.update_mesh();
+    }
+
+    fn set_revolve_segments(&mut self, n: usize) {
+        if !(3..=MAX_SEGMENTS).contains(&n) {
+            return;
+        }
+
+        self.revolve_segments = n;
+        self.update_mesh();
+    }
+
     fn set_splitnum(&mut self, n: usize) {
         if !(2..MAX_POINTS).contains(&n) {
             return;
@@ -251,6 +435,13 @@ pub fn start() -> Result<(), JsValue> {
     canvas.add_event_listener_with_callback("mousedown", handler.as_ref().unchecked_ref())?;
     handler.forget();
 
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::WheelEvent| {
+        scene_.borrow_mut().scroll_handler(event);
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
     // input handlers
     let scene_ = scene.clone();
     let handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
@@ -291,6 +482,60 @@ pub fn start() -> Result<(), JsValue> {
                 };
                 scene.update();
             }
+            "view" => {
+                let view = match val.as_str() {
+                    "edit" => View::Edit,
+                    "revolve" => View::Revolve,
+                    _ => {
+                        return;
+                    }
+                };
+                scene_.borrow_mut().set_view(view);
+            }
+            "angle" => {
+                if let Ok(d) = val.parse() {
+                    scene_.borrow_mut().set_revolve_angle(d);
+                }
+            }
+            "segments" => {
+                if let Ok(n) = val.parse() {
+                    scene_.borrow_mut().set_revolve_segments(n);
+                }
+            }
+            "animate" => {
+                scene_.borrow_mut().set_playing(targ.checked());
+            }
+            "duration" => {
+                if let Ok(d) = val.parse() {
+                    scene_.borrow_mut().set_duration(d);
+                }
+            }
+            "speed" | "speedcurve" => {
+                let document = web_sys::window().unwrap().document().unwrap();
+                let custom = document
+                    .get_element_by_id("inp_speedcurve")
+                    .and_then(|e| e.dyn_into::<HtmlInputElement>().ok())
+                    .map(|e| e.value())
+                    .unwrap_or_default();
+                let kind = document
+                    .query_selector("input[name=speed]:checked")
+                    .ok()
+                    .flatten()
+                    .and_then(|e| e.dyn_into::<HtmlInputElement>().ok())
+                    .map(|e| e.value())
+                    .unwrap_or_default();
+
+                let profile = match kind.as_str() {
+                    "constant" => anim::SpeedProfile::Constant,
+                    "ease" => anim::SpeedProfile::EaseInOut,
+                    "custom" => match anim::SpeedProfile::parse_custom(&custom) {
+                        Some(p) => p,
+                        None => return,
+                    },
+                    _ => return,
+                };
+                scene_.borrow_mut().speed_profile = profile;
+            }
             _ => {}
         }
     }) as Box<dyn FnMut(_)>);
@@ -298,10 +543,21 @@ pub fn start() -> Result<(), JsValue> {
 
     handler.forget();
 
+    let performance = web_sys::window()
+        .unwrap()
+        .performance()
+        .ok_or("performance not available")?;
+    let mut last_time = performance.now();
+
     let closure = Rc::new(RefCell::new(None));
     let closure_ = closure.clone();
     *closure_.borrow_mut() = Some(Closure::<dyn FnMut() -> Result<i32, JsValue>>::new(
         move || {
+            let now = performance.now();
+            let dt = ((now - last_time) / 1000.0) as f32;
+            last_time = now;
+
+            scene.borrow_mut().tick(dt);
             scene.borrow().draw();
             request_animation_frame(closure.borrow().as_ref().unwrap())
         },
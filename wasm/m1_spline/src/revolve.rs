@@ -0,0 +1,75 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts;
+
+pub struct RevolvedMesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u16>,
+}
+
+// profile points closer than this to the axis are closed with a single pole vertex
+const AXIS_EPS: f32 = 1e-2;
+
+// revolve the profile (x: radius, y: height) around the y axis by `angle` radians
+pub fn revolve(profile: &[Vec2], angle: f32, segments: usize) -> RevolvedMesh {
+    let n = profile.len();
+    let full = angle >= consts::TAU - 1e-4;
+    let columns = if full { segments } else { segments + 1 };
+
+    // 2D normals of the profile, rotated along with it
+    let normals_2d = (0..n)
+        .map(|i| {
+            let d = profile[(i + 1).min(n - 1)] - profile[i.saturating_sub(1)];
+            if d.norm() > 1e-8 {
+                Vec2::new(d.y, -d.x).normalize()
+            } else {
+                Vec2::new(1.0, 0.0)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut vertices = Vec::new();
+    let mut normals = Vec::new();
+
+    // first vertex index of each profile sample, and whether it is a pole
+    let mut rings = Vec::with_capacity(n);
+    for (i, (p, m)) in profile.iter().zip(&normals_2d).enumerate() {
+        let at_end = i == 0 || i == n - 1;
+        let is_pole = at_end && p.x.abs() < AXIS_EPS;
+        rings.push((vertices.len() as u16, is_pole));
+
+        if is_pole {
+            vertices.push(Vec3::new(0.0, p.y, 0.0));
+            normals.push(Vec3::new(0.0, if m.y < 0.0 { -1.0 } else { 1.0 }, 0.0));
+            continue;
+        }
+
+        for j in 0..columns {
+            let t = j as f32 / segments as f32 * angle;
+            let (s, c) = t.sin_cos();
+            vertices.push(Vec3::new(p.x * c, p.y, p.x * s));
+            normals.push(Vec3::new(m.x * c, m.y, m.x * s));
+        }
+    }
+
+    let mut indices = Vec::new();
+    for w in rings.windows(2) {
+        let (a, a_pole) = w[0];
+        let (b, b_pole) = w[1];
+        for j in 0..segments as u16 {
+            let k = (j + 1) % columns as u16;
+            match (a_pole, b_pole) {
+                (true, true) => {}
+                (true, false) => indices.extend([a, b + k, b + j]),
+                (false, true) => indices.extend([a + j, a + k, b]),
+                (false, false) => indices.extend([a + j, a + k, b + k, a + j, b + k, b + j]),
+            }
+        }
+    }
+
+    RevolvedMesh {
+        vertices,
+        normals,
+        indices,
+    }
+}
@@ -0,0 +1,509 @@
+use common::{camera::OrbitCamera, curves, vao::MyVAO};
+use glm::{Vec2, Vec3};
+use nalgebra_glm as glm;
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{WebGl2RenderingContext as GL, *};
+
+mod patch;
+mod sweep;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Patch,
+    Sweep,
+}
+
+struct Scene {
+    gl: Rc<GL>,
+    program: WebGlProgram,
+    program_lit: WebGlProgram,
+
+    vao_lin: MyVAO,
+    vao_pts: MyVAO,
+    vao_tri: MyVAO,
+
+    mvp_location: WebGlUniformLocation,
+    mvp_location_lit: WebGlUniformLocation,
+
+    camera: OrbitCamera,
+
+    mode: Mode,
+    splitnum: usize,
+
+    net: patch::ControlNet,
+    surfacetype: patch::SurfaceType,
+
+    curve_points: Vec<Vec3>,
+    curvetype: curves::CurveType,
+    frametype: sweep::FrameType,
+    profile: sweep::ProfileType,
+    profile_segments: usize,
+    radius: f32,
+
+    // index of the control point and the view-aligned plane it moves on
+    dragging: Option<(usize, Vec3)>,
+}
+
+const MAX_SPLIT: usize = 64;
+const MAX_NET: usize = 8;
+const MAX_PROFILE: usize = 32;
+const MAX_POINTS: usize = (MAX_SPLIT + 1) * (MAX_SPLIT + 1);
+impl Scene {
+    fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
+        canvas.set_width(CANVAS_SIZE);
+        canvas.set_height(CANVAS_SIZE);
+        let gl = canvas
+            .get_context("webgl2")?
+            .ok_or("Failed to get WebGl2RenderingContext")?
+            .dyn_into::<GL>()?;
+        let gl = Rc::new(gl);
+
+        let program = common::create_program(
+            &gl,
+            include_str!("shader/vertex.glsl"),
+            include_str!("shader/fragment.glsl"),
+        )?;
+        let program_lit = common::create_program(
+            &gl,
+            include_str!("shader/vertex_lit.glsl"),
+            include_str!("shader/fragment_lit.glsl"),
+        )?;
+
+        gl.enable(GL::DEPTH_TEST);
+        gl.depth_func(GL::LEQUAL);
+
+        let vao_lin = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 2)?;
+        let vao_pts = MyVAO::new(gl.clone(), MAX_NET * MAX_NET, MAX_NET * MAX_NET)?;
+        let vao_tri = MyVAO::new_with_normal(gl.clone(), MAX_POINTS, MAX_SPLIT * MAX_SPLIT * 6)?;
+
+        let mvp_location = gl
+            .get_uniform_location(&program, "mvpMatrix")
+            .ok_or("Failed to get uniform location")?;
+        let mvp_location_lit = gl
+            .get_uniform_location(&program_lit, "mvpMatrix")
+            .ok_or("Failed to get uniform location")?;
+
+        let mut camera = OrbitCamera::new(4.0);
+        camera.pitch = -0.5;
+
+        let mut r = Self {
+            gl,
+            program,
+            program_lit,
+            vao_lin,
+            vao_pts,
+            vao_tri,
+
+            mvp_location,
+            mvp_location_lit,
+
+            camera,
+
+            mode: Mode::Patch,
+            splitnum: 16,
+
+            net: patch::ControlNet::grid(4, 4),
+            surfacetype: patch::SurfaceType::Bezier,
+
+            // S shaped curve, the Frenet frame flips at its inflection
+            curve_points: vec![
+                Vec3::new(-1.2, -0.4, -0.2),
+                Vec3::new(-0.7, 0.5, 0.0),
+                Vec3::new(0.0, 0.0, 0.2),
+                Vec3::new(0.7, -0.5, 0.0),
+                Vec3::new(1.2, 0.4, -0.2),
+            ],
+            curvetype: curves::CurveType::CatmullRom(curves::CatmullRomParmType::Centripetal),
+            frametype: sweep::FrameType::RotationMinimizing,
+            profile: sweep::ProfileType::Circle,
+            profile_segments: 16,
+            radius: 0.15,
+
+            dragging: None,
+        };
+
+        r.update();
+
+        Ok(r)
+    }
+
+    fn points(&self) -> &[Vec3] {
+        match self.mode {
+            Mode::Patch => &self.net.points,
+            Mode::Sweep => &self.curve_points,
+        }
+    }
+
+    fn points_mut(&mut self) -> &mut [Vec3] {
+        match self.mode {
+            Mode::Patch => &mut self.net.points,
+            Mode::Sweep => &mut self.curve_points,
+        }
+    }
+
+    fn update(&mut self) {
+        // control points
+        let points = self.points();
+        let v = points
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let c = (0..points.len())
+            .flat_map(|i| match self.dragging {
+                Some((j, _)) if i == j => [1.0, 1.0, 0.0, 1.0],
+                _ => [0.0, 1.0, 0.0, 1.0],
+            })
+            .collect::<Vec<_>>();
+        let idx = (0..points.len() as u16).collect::<Vec<_>>();
+        self.vao_pts.send_data(&v, &c, &idx);
+
+        match self.mode {
+            Mode::Patch => self.update_patch(),
+            Mode::Sweep => self.update_sweep(),
+        }
+    }
+
+    fn update_patch(&mut self) {
+        let net = &self.net;
+
+        // control net
+        let v = net
+            .points
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let c = [1.0, 1.0, 1.0, 1.0].repeat(net.points.len());
+        let idx = (0..net.rows)
+            .flat_map(|i| {
+                (0..net.cols - 1).flat_map(move |j| [i * net.cols + j, i * net.cols + j + 1])
+            })
+            .chain((0..net.rows - 1).flat_map(|i| {
+                (0..net.cols).flat_map(move |j| [i * net.cols + j, (i + 1) * net.cols + j])
+            }))
+            .map(|i| i as u16)
+            .collect::<Vec<_>>();
+        self.vao_lin.send_data(&v, &c, &idx);
+
+        // surface
+        let mesh = patch::tessellate(net, self.surfacetype, self.splitnum);
+        self.send_mesh(&mesh.vertices, &mesh.normals, &mesh.indices);
+    }
+
+    fn update_sweep(&mut self) {
+        let curve = curves::make_curve(&self.curve_points, self.splitnum, self.curvetype);
+        let frames = sweep::make_frames(&curve, self.frametype);
+
+        // control polygon, curve and frame normals
+        let mut v = Vec::new();
+        let mut c = Vec::new();
+        let mut idx = Vec::new();
+        for p in self.curve_points.iter() {
+            v.extend([p.x, p.y, p.z]);
+            c.extend([0.5, 0.5, 0.5, 1.0]);
+        }
+        idx.extend((1..self.curve_points.len() as u16).flat_map(|i| [i - 1, i]));
+
+        let n = (v.len() / 3) as u16;
+        for p in curve.iter() {
+            v.extend([p.x, p.y, p.z]);
+            c.extend([1.0, 1.0, 1.0, 1.0]);
+        }
+        idx.extend((1..curve.len() as u16).flat_map(|i| [n + i - 1, n + i]));
+
+        for (p, f) in curve.iter().zip(&frames) {
+            let q = p + f.n * self.radius * 2.0;
+            let i = (v.len() / 3) as u16;
+            v.extend([p.x, p.y, p.z, q.x, q.y, q.z]);
+            c.extend([1.0, 0.3, 0.3, 1.0, 1.0, 0.3, 0.3, 1.0]);
+            idx.extend([i, i + 1]);
+        }
+        self.vao_lin.send_data(&v, &c, &idx);
+
+        let profile = sweep::make_profile(self.profile, self.profile_segments);
+        let mesh = sweep::sweep(&frames, &curve, &profile, self.radius);
+        self.send_mesh(&mesh.vertices, &mesh.normals, &mesh.indices);
+    }
+
+    fn send_mesh(&mut self, vertices: &[Vec3], normals: &[Vec3], indices: &[u16]) {
+        let v = vertices
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let n = normals
+            .iter()
+            .flat_map(|p| [p.x, p.y, p.z])
+            .collect::<Vec<_>>();
+        let c = [0.8, 0.8, 1.0, 1.0].repeat(vertices.len());
+        self.vao_tri.send_data(&v, &c, indices);
+        self.vao_tri.send_normal_data(&n);
+    }
+
+    fn draw(&self) {
+        self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
+        self.gl.clear_depth(1.0);
+        self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+
+        self.gl.use_program(Some(&self.program_lit));
+        self.camera
+            .send_mvp_matrix(&self.gl, &self.mvp_location_lit);
+        self.vao_tri.draw_elements(GL::TRIANGLES);
+
+        self.gl.use_program(Some(&self.program));
+        self.camera.send_mvp_matrix(&self.gl, &self.mvp_location);
+        self.vao_lin.draw_elements(GL::LINES);
+        self.vao_pts.draw_elements(GL::POINTS);
+        self.gl.flush();
+    }
+
+    fn mouse_handler(&mut self, event: web_sys::MouseEvent) {
+        if event.buttons() != 1 {
+            if self.dragging.take().is_some() {
+                self.update();
+            }
+            return;
+        }
+
+        let p = Vec2::new(
+            (event.offset_x() as f32 / CANVAS_SIZE as f32) * 2. - 1.,
+            -(event.offset_y() as f32 / CANVAS_SIZE as f32) * 2. + 1.,
+        );
+
+        if event.type_() == "mousedown" {
+            self.dragging = self.pick(&p).map(|i| (i, self.points()[i]));
+            self.update();
+            return;
+        }
+
+        match self.dragging {
+            Some((i, origin)) => {
+                let (eye, dir) = self.camera.ray(&p);
+                let normal = self.camera.forward();
+                let d = dir.dot(&normal);
+                if d.abs() < 1e-6 {
+                    return;
+                }
+                let t = (origin - eye).dot(&normal) / d;
+                self.points_mut()[i] = eye + dir * t;
+                self.update();
+            }
+            None => {
+                self.camera
+                    .move_camera((event.movement_x() as f32, event.movement_y() as f32), 0.0);
+            }
+        }
+    }
+
+    fn scroll_handler(&mut self, event: web_sys::WheelEvent) {
+        event.prevent_default();
+        self.camera
+            .move_camera((0.0, 0.0), event.delta_y() as f32 / 100.0);
+    }
+
+    // nearest control point on screen within a few pixels
+    fn pick(&self, p: &Vec2) -> Option<usize> {
+        const PICK_RADIUS: f32 = 16.0 / CANVAS_SIZE as f32 * 2.0;
+        self.points()
+            .iter()
+            .map(|v| self.camera.project(v))
+            .enumerate()
+            .filter(|(_, v)| v.z.abs() <= 1.0)
+            .map(|(i, v)| (i, (v.xy() - p).norm()))
+            .filter(|(_, d)| *d < PICK_RADIUS)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i)
+    }
+
+    fn set_splitnum(&mut self, n: usize) {
+        if !(1..=MAX_SPLIT).contains(&n) {
+            return;
+        }
+
+        self.splitnum = n;
+        self.update();
+    }
+
+    fn set_netsize(&mut self, rows: usize, cols: usize) {
+        if !(2..=MAX_NET).contains(&rows) || !(2..=MAX_NET).contains(&cols) {
+            return;
+        }
+
+        self.net = patch::ControlNet::grid(rows, cols);
+        self.dragging = None;
+        self.update();
+    }
+
+    fn set_profile_segments(&mut self, n: usize) {
+        if !(3..=MAX_PROFILE).contains(&n) {
+            return;
+        }
+
+        self.profile_segments = n;
+        self.update();
+    }
+
+    fn set_radius(&mut self, r: f32) {
+        if r.is_nan() || r <= 0.0 {
+            return;
+        }
+
+        self.radius = r;
+        self.update();
+    }
+}
+
+const CANVAS_SIZE: u32 = 1024;
+
+#[wasm_bindgen(start)]
+pub fn start() -> Result<(), JsValue> {
+    console_error_panic_hook::set_once();
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .get_element_by_id("canvas")
+        .ok_or("canvas not found")?
+        .dyn_into::<HtmlCanvasElement>()?;
+
+    let scene = Rc::new(RefCell::new(Scene::new(&canvas)?));
+
+    // mousemove handler
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+        scene_.borrow_mut().mouse_handler(event);
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("mousemove", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("mouseup", handler.as_ref().unchecked_ref())?;
+    canvas.add_event_listener_with_callback("mousedown", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::WheelEvent| {
+        scene_.borrow_mut().scroll_handler(event);
+    }) as Box<dyn FnMut(_)>);
+    canvas.add_event_listener_with_callback("wheel", handler.as_ref().unchecked_ref())?;
+    handler.forget();
+
+    // input handlers
+    let scene_ = scene.clone();
+    let handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        let targ = if let Some(e) = event.target() {
+            e
+        } else {
+            return;
+        };
+        let (targid, val) = if let Some(e) = targ.dyn_ref::<HtmlInputElement>() {
+            (e.name(), e.value())
+        } else if let Some(e) = targ.dyn_ref::<HtmlSelectElement>() {
+            (e.name(), e.value())
+        } else {
+            return;
+        };
+
+        let mut scene = scene_.borrow_mut();
+        match &*targid {
+            "split" => {
+                if let Ok(n) = val.parse() {
+                    scene.set_splitnum(n);
+                }
+            }
+            "rows" => {
+                let cols = scene.net.cols;
+                if let Ok(rows) = val.parse() {
+                    scene.set_netsize(rows, cols);
+                }
+            }
+            "cols" => {
+                let rows = scene.net.rows;
+                if let Ok(cols) = val.parse() {
+                    scene.set_netsize(rows, cols);
+                }
+            }
+            "surfacetype" => {
+                scene.surfacetype = match val.as_str() {
+                    "bezier" => patch::SurfaceType::Bezier,
+                    "bspline" => patch::SurfaceType::BSpline,
+                    _ => {
+                        return;
+                    }
+                };
+                scene.update();
+            }
+            "mode" => {
+                scene.mode = match val.as_str() {
+                    "patch" => Mode::Patch,
+                    "sweep" => Mode::Sweep,
+                    _ => {
+                        return;
+                    }
+                };
+                scene.dragging = None;
+                scene.update();
+            }
+            "curvetype" => {
+                scene.curvetype = match val.as_str() {
+                    "bezier" => curves::CurveType::Bezier,
+                    "catmullrom_uniform" => {
+                        curves::CurveType::CatmullRom(curves::CatmullRomParmType::Uniform)
+                    }
+                    "catmullrom_chordal" => {
+                        curves::CurveType::CatmullRom(curves::CatmullRomParmType::ChordLength)
+                    }
+                    "catmullrom_centripetal" => {
+                        curves::CurveType::CatmullRom(curves::CatmullRomParmType::Centripetal)
+                    }
+                    _ => {
+                        return;
+                    }
+                };
+                scene.update();
+            }
+            "frametype" => {
+                scene.frametype = match val.as_str() {
+                    "rmf" => sweep::FrameType::RotationMinimizing,
+                    "frenet" => sweep::FrameType::Frenet,
+                    _ => {
+                        return;
+                    }
+                };
+                scene.update();
+            }
+            "profile" => {
+                scene.profile = match val.as_str() {
+                    "circle" => sweep::ProfileType::Circle,
+                    "square" => sweep::ProfileType::Square,
+                    "star" => sweep::ProfileType::Star,
+                    _ => {
+                        return;
+                    }
+                };
+                scene.update();
+            }
+            "segments" => {
+                if let Ok(n) = val.parse() {
+                    scene.set_profile_segments(n);
+                }
+            }
+            "radius" => {
+                if let Ok(r) = val.parse() {
+                    scene.set_radius(r);
+                }
+            }
+            _ => {}
+        }
+    }) as Box<dyn FnMut(_)>);
+    document.add_event_listener_with_callback("change", handler.as_ref().unchecked_ref())?;
+
+    handler.forget();
+
+    let closure = Rc::new(RefCell::new(None));
+    let closure_ = closure.clone();
+    *closure_.borrow_mut() = Some(Closure::<dyn FnMut() -> Result<i32, JsValue>>::new(
+        move || {
+            scene.borrow().draw();
+            common::request_animation_frame(closure.borrow().as_ref().unwrap())
+        },
+    ));
+    common::request_animation_frame(closure_.borrow().as_ref().unwrap())?;
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use nalgebra_glm::Vec3;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SurfaceType {
+    Bezier,
+    // clamped uniform B-spline, cubic when the net is large enough
+    BSpline,
+}
+
+// control points are stored row-major: points[i * cols + j]
+pub struct ControlNet {
+    pub rows: usize,
+    pub cols: usize,
+    pub points: Vec<Vec3>,
+}
+
+impl ControlNet {
+    // flat grid on the xz plane with a bump in the middle
+    pub fn grid(rows: usize, cols: usize) -> Self {
+        let points = (0..rows)
+            .flat_map(|i| {
+                (0..cols).map(move |j| {
+                    let u = i as f32 / (rows - 1) as f32 * 2. - 1.;
+                    let v = j as f32 / (cols - 1) as f32 * 2. - 1.;
+                    let h = (1. - u * u) * (1. - v * v);
+                    Vec3::new(v, h * 0.8 - 0.2, u)
+                })
+            })
+            .collect();
+        Self { rows, cols, points }
+    }
+
+    pub fn at(&self, i: usize, j: usize) -> Vec3 {
+        self.points[i * self.cols + j]
+    }
+}
+
+pub struct SurfaceMesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u16>,
+}
+
+fn bernstein(n: usize, t: f32) -> Vec<f32> {
+    // de Casteljau style triangle of the basis functions
+    let mut b = vec![0.0; n + 1];
+    b[0] = 1.0;
+    for k in 1..=n {
+        for i in (0..=k).rev() {
+            let prev = if i > 0 { b[i - 1] } else { 0.0 };
+            b[i] = b[i] * (1. - t) + prev * t;
+        }
+    }
+    b
+}
+
+// values and derivatives of the Bernstein basis of degree count - 1
+fn bezier_basis(count: usize, t: f32) -> (Vec<f32>, Vec<f32>) {
+    let n = count - 1;
+    let b = bernstein(n, t);
+    if n == 0 {
+        return (b, vec![0.0]);
+    }
+    let lower = bernstein(n - 1, t);
+    let d = (0..=n)
+        .map(|i| {
+            let a = if i > 0 { lower[i - 1] } else { 0.0 };
+            let b = if i < n { lower[i] } else { 0.0 };
+            n as f32 * (a - b)
+        })
+        .collect();
+    (b, d)
+}
+
+// Cox-de Boor recursion on a clamped uniform knot vector
+fn bspline_basis(count: usize, t: f32) -> (Vec<f32>, Vec<f32>) {
+    let p = 3.min(count - 1);
+    let spans = count - p;
+    let knots = (0..count + p + 1)
+        .map(|i| i.saturating_sub(p).min(spans) as f32 / spans as f32)
+        .collect::<Vec<_>>();
+
+    // the last span is closed so that t == 1 evaluates to the end point
+    let span = (0..count)
+        .find(|&i| knots[i] <= t && (t < knots[i + 1] || (i + 1 == count && t <= knots[i + 1])))
+        .unwrap_or(count - 1);
+
+    let mut n = vec![0.0; count + p];
+    n[span] = 1.0;
+    let mut lower = n.clone();
+    for k in 1..=p {
+        lower.clone_from(&n);
+        for i in 0..count + p - k {
+            let l = knots[i + k] - knots[i];
+            let r = knots[i + k + 1] - knots[i + 1];
+            let a = if l > 0.0 {
+                (t - knots[i]) / l * n[i]
+            } else {
+                0.0
+            };
+            let b = if r > 0.0 {
+                (knots[i + k + 1] - t) / r * n[i + 1]
+            } else {
+                0.0
+            };
+            n[i] = a + b;
+        }
+    }
+
+    // derivative from the degree p - 1 basis
+    let d = (0..count)
+        .map(|i| {
+            let l = knots[i + p] - knots[i];
+            let r = knots[i + p + 1] - knots[i + 1];
+            let a = if l > 0.0 { lower[i] / l } else { 0.0 };
+            let b = if r > 0.0 { lower[i + 1] / r } else { 0.0 };
+            p as f32 * (a - b)
+        })
+        .collect();
+
+    n.truncate(count);
+    (n, d)
+}
+
+fn basis(surface: SurfaceType, count: usize, t: f32) -> (Vec<f32>, Vec<f32>) {
+    match surface {
+        SurfaceType::Bezier => bezier_basis(count, t),
+        SurfaceType::BSpline => bspline_basis(count, t),
+    }
+}
+
+// evaluate position and partial derivatives at (u, v)
+pub fn evaluate(net: &ControlNet, surface: SurfaceType, u: f32, v: f32) -> (Vec3, Vec3, Vec3) {
+    let (bu, du) = basis(surface, net.rows, u);
+    let (bv, dv) = basis(surface, net.cols, v);
+
+    let mut p = Vec3::zeros();
+    let mut pu = Vec3::zeros();
+    let mut pv = Vec3::zeros();
+    for i in 0..net.rows {
+        for j in 0..net.cols {
+            let c = net.at(i, j);
+            p += c * (bu[i] * bv[j]);
+            pu += c * (du[i] * bv[j]);
+            pv += c * (bu[i] * dv[j]);
+        }
+    }
+    (p, pu, pv)
+}
+
+pub fn tessellate(net: &ControlNet, surface: SurfaceType, split: usize) -> SurfaceMesh {
+    let mut vertices = Vec::with_capacity((split + 1) * (split + 1));
+    let mut normals = Vec::with_capacity((split + 1) * (split + 1));
+
+    for i in 0..=split {
+        for j in 0..=split {
+            let u = i as f32 / split as f32;
+            let v = j as f32 / split as f32;
+            let (p, pu, pv) = evaluate(net, surface, u, v);
+            let n = pu.cross(&pv);
+            vertices.push(p);
+            normals.push(if n.norm() > 1e-8 {
+                n.normalize()
+            } else {
+                Vec3::new(0.0, 1.0, 0.0)
+            });
+        }
+    }
+
+    let w = (split + 1) as u16;
+    let indices = (0..split as u16)
+        .flat_map(|i| {
+            (0..split as u16).flat_map(move |j| {
+                let a = i * w + j;
+                [a, a + 1, a + w + 1, a, a + w + 1, a + w]
+            })
+        })
+        .collect();
+
+    SurfaceMesh {
+        vertices,
+        normals,
+        indices,
+    }
+}
@@ -0,0 +1,198 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FrameType {
+    // double reflection method (Wang et al. 2008)
+    RotationMinimizing,
+    Frenet,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ProfileType {
+    Circle,
+    Square,
+    Star,
+}
+
+// frame at a curve sample: normal, binormal (the tangent completes it)
+pub struct Frame {
+    pub n: Vec3,
+    pub b: Vec3,
+}
+
+pub struct SweepMesh {
+    pub vertices: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub indices: Vec<u16>,
+}
+
+// closed counter-clockwise polygon with unit size and `segments` points. the polygons
+// keep their corners, so they have at least as many points as corners
+pub fn make_profile(profile: ProfileType, segments: usize) -> Vec<Vec2> {
+    match profile {
+        ProfileType::Circle => (0..segments)
+            .map(|i| {
+                let a = i as f32 / segments as f32 * consts::TAU;
+                Vec2::new(a.cos(), a.sin())
+            })
+            .collect(),
+        ProfileType::Square => subdivide(
+            &[
+                Vec2::new(1., -1.),
+                Vec2::new(1., 1.),
+                Vec2::new(-1., 1.),
+                Vec2::new(-1., -1.),
+            ]
+            .map(|v| v * consts::FRAC_1_SQRT_2),
+            segments,
+        ),
+        ProfileType::Star => {
+            let corners = (0..10)
+                .map(|i| {
+                    let a = i as f32 / 10. * consts::TAU;
+                    let r = if i % 2 == 0 { 1.0 } else { 0.45 };
+                    Vec2::new(a.cos(), a.sin()) * r
+                })
+                .collect::<Vec<_>>();
+            subdivide(&corners, segments)
+        }
+    }
+}
+
+// points along a closed polygon of equal sides, spread over the sides as evenly as possible
+fn subdivide(corners: &[Vec2], segments: usize) -> Vec<Vec2> {
+    let n = corners.len();
+    let total = segments.max(n);
+    (0..n)
+        .flat_map(|c| {
+            let a = corners[c];
+            let b = corners[(c + 1) % n];
+            let k = total / n + usize::from(c < total % n);
+            (0..k).map(move |i| a + (b - a) * (i as f32 / k as f32))
+        })
+        .collect()
+}
+
+fn tangents(curve: &[Vec3]) -> Vec<Vec3> {
+    let n = curve.len();
+    (0..n)
+        .map(|i| {
+            let d = curve[(i + 1).min(n - 1)] - curve[i.saturating_sub(1)];
+            if d.norm() > 1e-8 {
+                d.normalize()
+            } else {
+                Vec3::new(1.0, 0.0, 0.0)
+            }
+        })
+        .collect()
+}
+
+fn any_perpendicular(t: &Vec3) -> Vec3 {
+    let a = if t.x.abs() < 0.9 {
+        Vec3::new(1.0, 0.0, 0.0)
+    } else {
+        Vec3::new(0.0, 1.0, 0.0)
+    };
+    t.cross(&a).normalize()
+}
+
+fn rotation_minimizing_frames(curve: &[Vec3]) -> Vec<Frame> {
+    let t = tangents(curve);
+    let mut r = vec![any_perpendicular(&t[0])];
+
+    for i in 0..curve.len() - 1 {
+        let v1 = curve[i + 1] - curve[i];
+        let c1 = v1.dot(&v1);
+        if c1 < 1e-12 {
+            r.push(r[i]);
+            continue;
+        }
+        let rl = r[i] - v1 * (2. / c1 * v1.dot(&r[i]));
+        let tl = t[i] - v1 * (2. / c1 * v1.dot(&t[i]));
+
+        let v2 = t[i + 1] - tl;
+        let c2 = v2.dot(&v2);
+        r.push(if c2 < 1e-12 {
+            rl
+        } else {
+            rl - v2 * (2. / c2 * v2.dot(&rl))
+        });
+    }
+
+    t.into_iter()
+        .zip(r)
+        .map(|(t, n)| Frame { n, b: t.cross(&n) })
+        .collect()
+}
+
+fn frenet_frames(curve: &[Vec3]) -> Vec<Frame> {
+    let t = tangents(curve);
+    let n = curve.len();
+
+    let mut prev = any_perpendicular(&t[0]);
+    (0..n)
+        .map(|i| {
+            // binormal from the first and second differences
+            let d1 = curve[(i + 1).min(n - 1)] - curve[i.saturating_sub(1)];
+            let d2 = curve[(i + 1).min(n - 1)] - curve[i] * 2. + curve[i.saturating_sub(1)];
+            let b = d1.cross(&d2);
+            let nrm = if b.norm() > 1e-8 {
+                b.normalize().cross(&t[i])
+            } else {
+                // undefined on straight parts, keep the last one
+                (prev - t[i] * prev.dot(&t[i])).normalize()
+            };
+            prev = nrm;
+            Frame {
+                n: nrm,
+                b: t[i].cross(&nrm),
+            }
+        })
+        .collect()
+}
+
+pub fn make_frames(curve: &[Vec3], frame: FrameType) -> Vec<Frame> {
+    match frame {
+        FrameType::RotationMinimizing => rotation_minimizing_frames(curve),
+        FrameType::Frenet => frenet_frames(curve),
+    }
+}
+
+pub fn sweep(frames: &[Frame], curve: &[Vec3], profile: &[Vec2], radius: f32) -> SweepMesh {
+    let k = profile.len();
+
+    // outward normals of the closed profile
+    let profile_normals = (0..k)
+        .map(|j| {
+            let d = profile[(j + 1) % k] - profile[(j + k - 1) % k];
+            Vec2::new(d.y, -d.x).normalize()
+        })
+        .collect::<Vec<_>>();
+
+    let mut vertices = Vec::with_capacity(curve.len() * k);
+    let mut normals = Vec::with_capacity(curve.len() * k);
+    for (p, f) in curve.iter().zip(frames) {
+        for (q, m) in profile.iter().zip(&profile_normals) {
+            vertices.push(p + (f.n * q.x + f.b * q.y) * radius);
+            normals.push(f.n * m.x + f.b * m.y);
+        }
+    }
+
+    let k = k as u16;
+    let indices = (0..curve.len() as u16 - 1)
+        .flat_map(|i| {
+            (0..k).flat_map(move |j| {
+                let a = i * k + j;
+                let b = i * k + (j + 1) % k;
+                [a, b, b + k, a, b + k, a + k]
+            })
+        })
+        .collect();
+
+    SweepMesh {
+        vertices,
+        normals,
+        indices,
+    }
+}
@@ -0,0 +1,237 @@
+use cgmath::Point3;
+use m3_implicit::{marching_tetrahedra, Polygons};
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+const SPLIT: usize = 100;
+const RUNS: u32 = 5;
+
+type Polygonizer = fn(f32, usize, &dyn Fn(&Point3<f32>) -> f32) -> Polygons;
+
+// the previous implementation, evaluating both ends of every edge into nested vectors
+fn legacy(range: f32, split: usize, func: &dyn Fn(&Point3<f32>) -> f32) -> Polygons {
+    let grid_size = range * 2.0 / split as f32;
+
+    let mut vertices = Vec::new();
+
+    // find intersections
+    const EDGE_DIRECTIONS: [(usize, usize, usize); 7] = [
+        (1, 0, 0), // X
+        (0, 1, 0), // Y
+        (0, 0, 1), // Z
+        (1, 1, 0), // XY
+        (0, 1, 1), // YZ
+        (1, 0, 1), // XZ
+        (1, 1, 1), // XYZ
+    ];
+    let verts = (0..=split)
+        .map(|x| {
+            (0..=split)
+                .map(|y| {
+                    (0..=split)
+                        .map(|z| {
+                            EDGE_DIRECTIONS
+                                .iter()
+                                .map(|(dx, dy, dz)| {
+                                    let p0 = Point3 {
+                                        x: x as f32 * grid_size - range,
+                                        y: y as f32 * grid_size - range,
+                                        z: z as f32 * grid_size - range,
+                                    };
+                                    let p1 = Point3 {
+                                        x: (x + dx) as f32 * grid_size - range,
+                                        y: (y + dy) as f32 * grid_size - range,
+                                        z: (z + dz) as f32 * grid_size - range,
+                                    };
+                                    let v0 = func(&p0);
+                                    let v1 = func(&p1);
+                                    if v0 * v1 < 0.0 {
+                                        let t = v0 / (v0 - v1);
+                                        let id = vertices.len();
+                                        vertices.push(p0 + (p1 - p0) * t);
+                                        Some(id)
+                                    } else {
+                                        None
+                                    }
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // add faces
+    const GRID_FACES: [[usize; 3]; 18] = [
+        [4, 6, 17],
+        [1, 6, 12],
+        [3, 6, 16],
+        [0, 6, 9],
+        [5, 6, 18],
+        [2, 6, 15],
+        [2, 4, 14],
+        [1, 4, 11],
+        [11, 12, 17],
+        [10, 12, 16],
+        [7, 9, 16],
+        [8, 9, 18],
+        [0, 5, 8],
+        [2, 5, 13],
+        [1, 3, 10],
+        [0, 3, 7],
+        [14, 15, 17],
+        [13, 15, 18],
+    ];
+
+    const GRID_BODIES: [[usize; 4]; 6] = [
+        [0, 5, 6, 16],
+        [0, 1, 7, 8],
+        [1, 2, 9, 14],
+        [2, 3, 10, 15],
+        [3, 4, 11, 12],
+        [4, 5, 13, 17],
+    ];
+
+    let mut faces = Vec::new();
+
+    for x in 0..split {
+        for y in 0..split {
+            for z in 0..split {
+                let mut grid_edges = verts[x][y][z].clone();
+                grid_edges.extend([
+                    // X
+                    verts[x + 1][y][z][1], // Y
+                    verts[x + 1][y][z][2], // Z
+                    verts[x + 1][y][z][4], // YZ
+                    // Y
+                    verts[x][y + 1][z][0], // X
+                    verts[x][y + 1][z][2], // Z
+                    verts[x][y + 1][z][5], // XZ
+                    // Z
+                    verts[x][y][z + 1][0],     // X
+                    verts[x][y][z + 1][1],     // Y
+                    verts[x][y][z + 1][3],     // XY
+                    verts[x + 1][y + 1][z][2], // XY -> Z
+                    verts[x][y + 1][z + 1][0], // YZ -> X
+                    verts[x + 1][y][z + 1][1], // XZ -> Y
+                ]);
+
+                let edges = GRID_FACES
+                    .iter()
+                    .map(|i| {
+                        let v = i.iter().filter_map(|&i| grid_edges[i]).collect::<Vec<_>>();
+                        // assert_eq!(v.len() % 2, 0);
+                        if v.len() == 2 {
+                            Some((v[0], v[1]))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                for i in GRID_BODIES {
+                    let mut f = i.iter().filter_map(|&i| edges[i]).collect::<Vec<_>>();
+                    if f.is_empty() {
+                        continue;
+                    }
+                    // assert!(f.len() != 1 && f.len() != 2);
+                    if f.len() < 3 {
+                        continue;
+                    }
+                    let e0 = f.pop().unwrap();
+                    let (mut pr, mut cr) = e0;
+                    let mut e = vec![pr, cr];
+                    for _ in 0..f.len() {
+                        if cr == e0.0 {
+                            break;
+                        }
+                        for ff in f.iter() {
+                            if ff.0 == cr && ff.1 != pr {
+                                e.push(ff.1);
+                                pr = cr;
+                                cr = ff.1;
+                                break;
+                            }
+                            if ff.1 == cr && ff.0 != pr {
+                                e.push(ff.0);
+                                pr = cr;
+                                cr = ff.0;
+                                break;
+                            }
+                        }
+                    }
+                    if e.first() == e.last() {
+                        e.pop();
+                    }
+
+                    if e.len() == 4 {
+                        e = vec![e[0], e[1], e[3], e[1], e[2], e[3]];
+                    }
+                    for es in e.chunks(3) {
+                        faces.push([es[0], es[1], es[2]]);
+                    }
+                }
+            }
+        }
+    }
+
+    (vertices, faces)
+}
+
+// the triangles as coordinates, rotated to start at their smallest vertex and sorted,
+// so that meshes with different vertex numbering can be compared
+fn triangles((vertices, faces): &Polygons) -> Vec<[[u32; 3]; 3]> {
+    let mut triangles = faces
+        .iter()
+        .map(|f| {
+            let mut t = f.map(|i| {
+                let p = vertices[i];
+                [p.x.to_bits(), p.y.to_bits(), p.z.to_bits()]
+            });
+            let first = (0..3).min_by_key(|&k| t[k]).unwrap();
+            t.rotate_left(first);
+            t
+        })
+        .collect::<Vec<_>>();
+    triangles.sort();
+    triangles
+}
+
+fn measure(name: &str, polygonize: Polygonizer) -> (Polygons, Duration) {
+    // a torus around the y axis, the default function of the page
+    let calls = Cell::new(0usize);
+    let torus = |p: &Point3<f32>| {
+        calls.set(calls.get() + 1);
+        let r = (p.x * p.x + p.z * p.z).sqrt() - 0.5;
+        (r * r + p.y * p.y).sqrt() - 0.2
+    };
+    let polygons = polygonize(1.0, SPLIT, &torus);
+    let evaluations = calls.get();
+
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        std::hint::black_box(polygonize(1.0, SPLIT, &torus));
+    }
+    let time = start.elapsed() / RUNS;
+    println!(
+        "{:>8}: {:>9.2?} per run, {:>9} evaluations, {} vertices, {} triangles",
+        name,
+        time,
+        evaluations,
+        polygons.0.len(),
+        polygons.1.len()
+    );
+    (polygons, time)
+}
+
+fn main() {
+    println!("marching tetrahedra, split = {}", SPLIT);
+    let (old, old_time) = measure("legacy", legacy);
+    let (new, new_time) = measure("current", marching_tetrahedra);
+    assert!(triangles(&old) == triangles(&new), "the meshes differ");
+    println!(
+        "same triangles, {:.1}x faster",
+        old_time.as_secs_f64() / new_time.as_secs_f64()
+    );
+}
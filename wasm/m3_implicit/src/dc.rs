@@ -0,0 +1,141 @@
+use cgmath::prelude::InnerSpace;
+use cgmath::{Point3, Vector3};
+use nalgebra_glm as glm;
+
+use crate::{gradient, Polygons};
+
+// singular values below this fraction of the largest are dropped when solving the QEF,
+// so that flat and edge-only cells do not move the vertex along the free directions
+const SVD_THRESHOLD: f32 = 0.1;
+
+// the point minimizing the squared distances to the tangent planes (p_i, n_i),
+// solved relative to their mass point with a truncated pseudo-inverse
+fn solve_qef(planes: &[(Point3<f32>, Vector3<f32>)]) -> Point3<f32> {
+    let c = planes
+        .iter()
+        .fold(glm::Vec3::zeros(), |c, (p, _)| c + glm::vec3(p.x, p.y, p.z))
+        / planes.len() as f32;
+    let mut a = glm::Mat3::zeros();
+    let mut b = glm::Vec3::zeros();
+    for (p, n) in planes {
+        let n = glm::vec3(n.x, n.y, n.z);
+        a += n * n.transpose();
+        b += n * n.dot(&(glm::vec3(p.x, p.y, p.z) - c));
+    }
+    let svd = a.svd(true, true);
+    let eps = svd.singular_values.max() * SVD_THRESHOLD;
+    let x = c + svd.solve(&b, eps.max(1e-12)).unwrap_or_default();
+    Point3::new(x.x, x.y, x.z)
+}
+
+// dual contouring over the (split+1)^3 samples in [-range, range]^3, negative inside.
+// one vertex per cell where the QEF of its crossings puts it, one quad per crossed edge
+pub fn polygonize(range: f32, split: usize, func: &dyn Fn(&Point3<f32>) -> f32) -> Polygons {
+    let grid_size = range * 2.0 / split as f32;
+    let n = split + 1;
+    let index = |x: usize, y: usize, z: usize| (x * n + y) * n + z;
+    let cell_index = |x: usize, y: usize, z: usize| (x * split + y) * split + z;
+    let point = |c: [usize; 3]| {
+        Point3::new(
+            c[0] as f32 * grid_size - range,
+            c[1] as f32 * grid_size - range,
+            c[2] as f32 * grid_size - range,
+        )
+    };
+
+    let mut samples = vec![0.0; n * n * n];
+    for x in 0..n {
+        for y in 0..n {
+            for z in 0..n {
+                samples[index(x, y, z)] = func(&point([x, y, z]));
+            }
+        }
+    }
+    let sample = |c: [usize; 3]| samples[index(c[0], c[1], c[2])];
+
+    // hermite data of a cell: where its edges cross the surface and the normals there
+    let h = grid_size * 1e-2;
+    let mut vertices = vec![];
+    let mut cell_vertex = vec![usize::MAX; split * split * split];
+    for x in 0..split {
+        for y in 0..split {
+            for z in 0..split {
+                let mut planes = vec![];
+                for axis in 0..3 {
+                    for k in 0..4 {
+                        let mut a = [x, y, z];
+                        a[(axis + 1) % 3] += k & 1;
+                        a[(axis + 2) % 3] += k >> 1;
+                        let mut b = a;
+                        b[axis] += 1;
+                        let (va, vb) = (sample(a), sample(b));
+                        if (va < 0.0) != (vb < 0.0) {
+                            let (pa, pb) = (point(a), point(b));
+                            let p = pa + (pb - pa) * (va / (va - vb));
+                            planes.push((p, gradient(func, &p, h)));
+                        }
+                    }
+                }
+                if planes.is_empty() {
+                    continue;
+                }
+
+                // keep the vertex in its cell, the QEF can be far off when the normals disagree
+                let lo = point([x, y, z]);
+                let v = solve_qef(&planes);
+                vertices.push(Point3::new(
+                    v.x.clamp(lo.x, lo.x + grid_size),
+                    v.y.clamp(lo.y, lo.y + grid_size),
+                    v.z.clamp(lo.z, lo.z + grid_size),
+                ));
+                cell_vertex[cell_index(x, y, z)] = vertices.len() - 1;
+            }
+        }
+    }
+
+    // a quad around every crossed edge that has cells on all four sides
+    let mut faces = vec![];
+    for x in 0..n {
+        for y in 0..n {
+            for z in 0..n {
+                let a = [x, y, z];
+                for axis in 0..3 {
+                    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                    if a[axis] >= split || a[u] == 0 || a[v] == 0 || a[u] >= split || a[v] >= split
+                    {
+                        continue;
+                    }
+                    let mut b = a;
+                    b[axis] += 1;
+                    let inside = sample(a) < 0.0;
+                    if inside == (sample(b) < 0.0) {
+                        continue;
+                    }
+
+                    // counterclockwise around +axis, seen from the positive side
+                    let mut quad = [(0, 0), (1, 0), (1, 1), (0, 1)].map(|(du, dv)| {
+                        let mut c = a;
+                        c[u] = c[u] + du - 1;
+                        c[v] = c[v] + dv - 1;
+                        cell_vertex[cell_index(c[0], c[1], c[2])]
+                    });
+                    if !inside {
+                        quad.reverse();
+                    }
+                    // split along the shorter diagonal
+                    let d02 = (vertices[quad[0]] - vertices[quad[2]]).magnitude2();
+                    let d13 = (vertices[quad[1]] - vertices[quad[3]]).magnitude2();
+                    if d02 <= d13 {
+                        faces.push([quad[0], quad[1], quad[2]]);
+                        faces.push([quad[0], quad[2], quad[3]]);
+                    } else {
+                        faces.push([quad[0], quad[1], quad[3]]);
+                        faces.push([quad[1], quad[2], quad[3]]);
+                    }
+                }
+            }
+        }
+    }
+
+    (vertices, faces)
+}
@@ -0,0 +1,461 @@
+use cgmath::Point3;
+
+// a scalar field compiled from an expression of x, y and z
+pub type Function = Box<dyn Fn(&Point3<f32>) -> f32>;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Token {
+    Num(f32),
+    Ident(usize, usize),
+    Op(char),
+    End,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // levels of the tree being parsed. parentheses, signs, powers and calls nest, and
+    // chained operators deepen it as well, all of which recurses when building and evaluating
+    depth: usize,
+}
+
+enum Node {
+    Num(f32),
+    // index into the coordinates
+    Var(usize),
+    Neg(Box<Node>),
+    Bin(char, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+// name, minimum and maximum argument count
+const FUNCTIONS: [(&str, usize, usize); 20] = [
+    ("sin", 1, 1),
+    ("cos", 1, 1),
+    ("tan", 1, 1),
+    ("asin", 1, 1),
+    ("acos", 1, 1),
+    ("atan", 1, 1),
+    ("atan2", 2, 2),
+    ("sqrt", 1, 1),
+    ("abs", 1, 1),
+    ("exp", 1, 1),
+    ("log", 1, 1),
+    ("floor", 1, 1),
+    ("sign", 1, 1),
+    ("pow", 2, 2),
+    ("mod", 2, 2),
+    ("min", 2, usize::MAX),
+    ("max", 2, usize::MAX),
+    ("length", 1, usize::MAX),
+    ("clamp", 3, 3),
+    ("mix", 3, 3),
+];
+
+// deeper expressions are rejected rather than overflowing the stack
+const MAX_DEPTH: usize = 256;
+
+// positions in errors are 1-based columns
+fn error(column: usize, msg: &str) -> String {
+    format!("column {}: {}", column + 1, msg)
+}
+
+fn tokenize(src: &str) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens = vec![];
+    // tokens are positioned by character for the error messages, and sliced by byte
+    let chars = src.char_indices().collect::<Vec<_>>();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut j = i;
+            while j < chars.len() {
+                let d = chars[j].1;
+                let exponent_sign = (d == '+' || d == '-') && matches!(chars[j - 1].1, 'e' | 'E');
+                if !(d.is_ascii_digit() || d == '.' || d == 'e' || d == 'E' || exponent_sign) {
+                    break;
+                }
+                j += 1;
+            }
+            let end = chars.get(j).map_or(src.len(), |c| c.0);
+            let v = src[start..end]
+                .parse()
+                .map_err(|_| error(i, &format!("invalid number '{}'", &src[start..end])))?;
+            tokens.push((i, Token::Num(v)));
+            i = j;
+        } else if c.is_alphabetic() || c == '_' {
+            let mut j = i;
+            while j < chars.len() && (chars[j].1.is_alphanumeric() || chars[j].1 == '_') {
+                j += 1;
+            }
+            let end = chars.get(j).map_or(src.len(), |c| c.0);
+            tokens.push((i, Token::Ident(start, end)));
+            i = j;
+        } else if "+-*/%^(),".contains(c) {
+            tokens.push((i, Token::Op(c)));
+            i += 1;
+        } else {
+            return Err(error(i, &format!("unexpected character '{}'", c)));
+        }
+    }
+    tokens.push((chars.len(), Token::End));
+    Ok(tokens)
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Token {
+        self.tokens[self.pos].1
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> Token {
+        let t = self.peek();
+        if t != Token::End {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek() == Token::Op(c) {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("expected '{}'", c)))
+        }
+    }
+
+    fn unexpected(&self, msg: &str) -> String {
+        let found = match self.peek() {
+            Token::End => "end of input".to_string(),
+            Token::Num(v) => format!("'{}'", v),
+            Token::Ident(a, b) => format!("'{}'", &self.src[a..b]),
+            Token::Op(c) => format!("'{}'", c),
+        };
+        error(self.column(), &format!("{}, found {}", msg, found))
+    }
+
+    // one more level of the tree, an error stops the whole parse so it is not undone
+    fn enter(&mut self) -> Result<(), String> {
+        if self.depth == MAX_DEPTH {
+            return Err(error(self.column(), "expression is nested too deeply"));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut lhs = self.term()?;
+        while let Token::Op(c @ ('+' | '-')) = self.peek() {
+            self.enter()?;
+            self.next();
+            lhs = Node::Bin(c, Box::new(lhs), Box::new(self.term()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    // term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Node, String> {
+        let depth = self.depth;
+        let mut lhs = self.unary()?;
+        while let Token::Op(c @ ('*' | '/' | '%')) = self.peek() {
+            self.enter()?;
+            self.next();
+            lhs = Node::Bin(c, Box::new(lhs), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(lhs)
+    }
+
+    // unary := '-' unary | power, so that -x^2 is -(x^2)
+    // every nested expression passes through here
+    fn unary(&mut self) -> Result<Node, String> {
+        self.enter()?;
+        let r = self.signed()?;
+        self.depth -= 1;
+        Ok(r)
+    }
+
+    fn signed(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Token::Op('-') => {
+                self.next();
+                Ok(Node::Neg(Box::new(self.unary()?)))
+            }
+            Token::Op('+') => {
+                self.next();
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    // power := atom ('^' unary)?, right associative
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.peek() == Token::Op('^') {
+            self.next();
+            return Ok(Node::Bin('^', Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        let column = self.column();
+        match self.peek() {
+            Token::Num(v) => {
+                self.next();
+                Ok(Node::Num(v))
+            }
+            Token::Op('(') => {
+                self.next();
+                let e = self.expr()?;
+                self.expect(')')?;
+                Ok(e)
+            }
+            Token::Ident(a, b) => {
+                self.next();
+                let name = &self.src[a..b];
+                match name {
+                    "x" => return Ok(Node::Var(0)),
+                    "y" => return Ok(Node::Var(1)),
+                    "z" => return Ok(Node::Var(2)),
+                    "pi" => return Ok(Node::Num(std::f32::consts::PI)),
+                    _ => {}
+                }
+                let Some(&(name, min, max)) = FUNCTIONS.iter().find(|f| f.0 == name) else {
+                    return Err(error(column, &format!("unknown name '{}'", name)));
+                };
+
+                self.expect('(')?;
+                let mut args = vec![self.expr()?];
+                while self.peek() == Token::Op(',') {
+                    self.next();
+                    args.push(self.expr()?);
+                }
+                self.expect(')')?;
+
+                if args.len() < min || args.len() > max {
+                    let count = match (min, max) {
+                        (a, b) if a == b => format!("{}", a),
+                        (a, usize::MAX) => format!("at least {}", a),
+                        (a, b) => format!("{} to {}", a, b),
+                    };
+                    let s = if min == 1 && max == 1 { "" } else { "s" };
+                    return Err(error(
+                        column,
+                        &format!("{} takes {} argument{}, got {}", name, count, s, args.len()),
+                    ));
+                }
+                Ok(Node::Call(name, args))
+            }
+            _ => Err(self.unexpected("expected a value")),
+        }
+    }
+}
+
+type Compiled = Box<dyn Fn(&[f32; 3]) -> f32>;
+
+// turn the tree into nested closures, so that evaluating does not walk it again
+fn build(node: Node) -> Compiled {
+    match node {
+        Node::Num(v) => Box::new(move |_| v),
+        Node::Var(i) => Box::new(move |p| p[i]),
+        Node::Neg(a) => {
+            let a = build(*a);
+            Box::new(move |p| -a(p))
+        }
+        Node::Bin(op, a, b) => {
+            let (a, b) = (build(*a), build(*b));
+            match op {
+                '+' => Box::new(move |p| a(p) + b(p)),
+                '-' => Box::new(move |p| a(p) - b(p)),
+                '*' => Box::new(move |p| a(p) * b(p)),
+                '/' => Box::new(move |p| a(p) / b(p)),
+                '%' => Box::new(move |p| a(p).rem_euclid(b(p))),
+                _ => Box::new(move |p| a(p).powf(b(p))),
+            }
+        }
+        Node::Call(name, args) => {
+            let mut args = args.into_iter().map(build).collect::<Vec<_>>();
+            if args.len() == 1 {
+                let a = args.pop().unwrap();
+                let f: fn(f32) -> f32 = match name {
+                    "sin" => f32::sin,
+                    "cos" => f32::cos,
+                    "tan" => f32::tan,
+                    "asin" => f32::asin,
+                    "acos" => f32::acos,
+                    "atan" => f32::atan,
+                    "sqrt" => f32::sqrt,
+                    "abs" => f32::abs,
+                    "exp" => f32::exp,
+                    "log" => f32::ln,
+                    "floor" => f32::floor,
+                    "sign" => f32::signum,
+                    "length" => f32::abs,
+                    _ => unreachable!(),
+                };
+                return Box::new(move |p| f(a(p)));
+            }
+            match name {
+                "atan2" => Box::new(move |p| args[0](p).atan2(args[1](p))),
+                "pow" => Box::new(move |p| args[0](p).powf(args[1](p))),
+                "mod" => Box::new(move |p| args[0](p).rem_euclid(args[1](p))),
+                "min" => Box::new(move |p| args.iter().map(|a| a(p)).fold(f32::INFINITY, f32::min)),
+                "max" => {
+                    Box::new(move |p| args.iter().map(|a| a(p)).fold(f32::NEG_INFINITY, f32::max))
+                }
+                "length" => {
+                    Box::new(move |p| args.iter().map(|a| a(p).powi(2)).sum::<f32>().sqrt())
+                }
+                "clamp" => Box::new(move |p| args[0](p).max(args[1](p)).min(args[2](p))),
+                _ => Box::new(move |p| {
+                    let t = args[2](p);
+                    args[0](p) * (1.0 - t) + args[1](p) * t
+                }),
+            }
+        }
+    }
+}
+
+// parse `src`, errors carry the column they were found at
+pub fn compile(src: &str) -> Result<Function, String> {
+    let mut parser = Parser {
+        src,
+        tokens: tokenize(src)?,
+        pos: 0,
+        depth: 0,
+    };
+    let node = parser.expr()?;
+    if parser.peek() != Token::End {
+        return Err(parser.unexpected("expected an operator"));
+    }
+    let f = build(node);
+    Ok(Box::new(move |p| f(&[p.x, p.y, p.z])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(src: &str, x: f32, y: f32, z: f32) -> f32 {
+        compile(src).unwrap()(&Point3::new(x, y, z))
+    }
+
+    fn close(src: &str, expected: f32) {
+        let v = eval(src, 3.0, 4.0, 5.0);
+        assert!(
+            (v - expected).abs() < 1e-5,
+            "{} = {}, expected {}",
+            src,
+            v,
+            expected
+        );
+    }
+
+    fn fails(src: &str, expected: &str) {
+        match compile(src) {
+            Ok(_) => panic!("{} compiled", src),
+            Err(e) => assert_eq!(e, expected, "{}", src),
+        }
+    }
+
+    #[test]
+    fn precedence() {
+        close("1+2*3", 7.0);
+        close("(1+2)*3", 9.0);
+        close("10-4-3", 3.0);
+        close("12/3/2", 2.0);
+        close("7%4*2", 6.0);
+        close("2^3^2", 512.0);
+        close("2*3^2", 18.0);
+    }
+
+    #[test]
+    fn unary_minus() {
+        close("-x^2", -9.0);
+        close("(-x)^2", 9.0);
+        close("2^-1", 0.5);
+        close("2*-y", -8.0);
+        close("--z", 5.0);
+        close("+x", 3.0);
+    }
+
+    #[test]
+    fn variables_and_functions() {
+        close("x + y * z", 23.0);
+        close("length(x, y)", 5.0);
+        close("length(-x)", 3.0);
+        close("min(z, x, y)", 3.0);
+        close("max(z, x, y)", 5.0);
+        close("clamp(z, 0, 1)", 1.0);
+        close("mix(0, 10, 0.25)", 2.5);
+        close("mod(-1, 3)", 2.0);
+        close("atan2(0, -1)", std::f32::consts::PI);
+        close("cos(pi)", -1.0);
+        close("1e-1 + 2.5E1", 25.1);
+    }
+
+    #[test]
+    fn unknown_names() {
+        fails("foo(x)", "column 1: unknown name 'foo'");
+        fails("x + w", "column 5: unknown name 'w'");
+    }
+
+    #[test]
+    fn wrong_arity() {
+        fails("sin(x, y)", "column 1: sin takes 1 argument, got 2");
+        fails("min(x)", "column 1: min takes at least 2 arguments, got 1");
+        fails(
+            "1 + clamp(x, 0)",
+            "column 5: clamp takes 3 arguments, got 2",
+        );
+    }
+
+    #[test]
+    fn error_positions() {
+        fails("1 + * 2", "column 5: expected a value, found '*'");
+        fails("(1 + 2", "column 7: expected ')', found end of input");
+        fails("1 2", "column 3: expected an operator, found '2'");
+        fails("sin x", "column 5: expected '(', found 'x'");
+        fails("x $ 1", "column 3: unexpected character '$'");
+        fails("1.2.3", "column 1: invalid number '1.2.3'");
+        fails("", "column 1: expected a value, found end of input");
+        // columns count characters, not bytes
+        fails("é * $", "column 5: unexpected character '$'");
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |n: usize| "(".repeat(n) + "x" + &")".repeat(n);
+        close(&nested(MAX_DEPTH - 1), 3.0);
+        fails(
+            &nested(10_000),
+            &format!("column {}: expression is nested too deeply", MAX_DEPTH + 1),
+        );
+        fails(
+            &"-".repeat(10_000),
+            &format!("column {}: expression is nested too deeply", MAX_DEPTH + 1),
+        );
+        // a long sum is a deep tree too
+        let sum = |n: usize| "x+".repeat(n) + "x";
+        close(&sum(MAX_DEPTH - 1), 3.0 * MAX_DEPTH as f32);
+        fails(
+            &sum(10_000),
+            &format!(
+                "column {}: expression is nested too deeply",
+                2 * MAX_DEPTH + 1
+            ),
+        );
+    }
+}
@@ -1,30 +1,68 @@
+use cgmath::prelude::InnerSpace;
 use cgmath::{Point3, Vector3};
-use cgmath::prelude::{InnerSpace, EuclideanSpace};
-use common::vao::MyVAO;
-use nalgebra_glm as glm;
-use std::{cell::RefCell, f32::consts, rc::Rc};
+use common::{camera::OrbitCamera, vao::MyVAO};
+use std::{cell::RefCell, rc::Rc};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{WebGl2RenderingContext as GL, *};
 
-
 use half_edge_mesh::HalfEdgeMesh;
 
+mod dc;
+mod expr;
+mod mc;
+mod octree;
+mod sdf;
+
 struct Scene {
     gl: Rc<GL>,
     program: WebGlProgram,
+    program_lit: WebGlProgram,
 
     vao_lin: MyVAO,
     vao_tri: MyVAO,
 
     mvp_location: WebGlUniformLocation,
+    mvp_location_lit: WebGlUniformLocation,
 
-    camera_dist: f32,
-    camera_pitch: f32,
-    camera_yaw: f32,
+    camera: OrbitCamera,
 
     mesh: HalfEdgeMesh,
+    function: expr::Function,
+    split: usize,
+    method: Method,
+    stats: MeshStats,
+    // unit normals of the mesh vertices by id, from the gradient of the field
+    normals: std::collections::HashMap<u32, Vector3<f32>>,
+}
+
+#[derive(Clone, Copy)]
+enum Method {
+    Tetrahedra,
+    Cubes,
+    DualContouring,
+    Octree,
+}
+
+// vertices and triangles, before they become a half-edge mesh
+pub type Polygons = (Vec<Point3<f32>>, Vec<[usize; 3]>);
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Default)]
+pub struct MeshStats {
+    pub vertices: u32,
+    pub triangles: u32,
+    // smallest triangle angle, in degrees
+    pub min_angle: f32,
+    // mean of the inradius to circumradius ratio, 1 for equilateral triangles
+    pub mean_quality: f32,
+    pub time_ms: f64,
+    // the mesh fits the buffers, larger ones are measured but not drawn
+    pub drawable: bool,
 }
 
+// a torus around the y axis
+const DEFAULT_FUNCTION: &str = "length(length(x, z) - 0.5, y) - 0.2";
+
 fn make_tetrahedron() -> HalfEdgeMesh {
     HalfEdgeMesh::from_tetrahedron_pts(
         Point3 {
@@ -50,9 +88,74 @@ fn make_tetrahedron() -> HalfEdgeMesh {
     )
 }
 
-// run Marching Tetrahedra algorithm
-fn make_model_implicit(range: f32, split: usize, func: fn(&Point3<f32>) -> f32) -> HalfEdgeMesh {
+// central differences with step `h`, pointing outward
+pub fn gradient(func: &dyn Fn(&Point3<f32>) -> f32, p: &Point3<f32>, h: f32) -> Vector3<f32> {
+    let d = |v: Vector3<f32>| (func(&(p + v * h)) - func(&(p - v * h))) / (2.0 * h);
+    Vector3::new(
+        d(Vector3::unit_x()),
+        d(Vector3::unit_y()),
+        d(Vector3::unit_z()),
+    )
+}
+
+fn make_model_implicit(
+    range: f32,
+    split: usize,
+    func: &dyn Fn(&Point3<f32>) -> f32,
+    method: Method,
+) -> Polygons {
+    match method {
+        Method::Tetrahedra => marching_tetrahedra(range, split, func),
+        Method::Cubes => mc::polygonize(range, split, func),
+        Method::DualContouring => dc::polygonize(range, split, func),
+        Method::Octree => octree::polygonize(range, split, func),
+    }
+}
+
+fn measure((vertices, faces): &Polygons) -> MeshStats {
+    let mut min_angle = f32::INFINITY;
+    let mut quality = 0.0;
+    for f in faces {
+        let p = f.map(|i| vertices[i]);
+        let l = [0, 1, 2].map(|k| (p[(k + 1) % 3] - p[k]).magnitude());
+        for k in 0..3 {
+            let (a, b, c) = (l[k], l[(k + 1) % 3], l[(k + 2) % 3]);
+            // angle opposite of `a`
+            let cos = ((b * b + c * c - a * a) / (2.0 * b * c)).clamp(-1.0, 1.0);
+            min_angle = min_angle.min(cos.acos().to_degrees());
+        }
+        let (a, b, c) = (l[0], l[1], l[2]);
+        quality += ((b + c - a) * (c + a - b) * (a + b - c) / (a * b * c)).max(0.0);
+    }
+    MeshStats {
+        vertices: vertices.len() as u32,
+        triangles: faces.len() as u32,
+        min_angle: if faces.is_empty() { 0.0 } else { min_angle },
+        mean_quality: quality / faces.len().max(1) as f32,
+        ..MeshStats::default()
+    }
+}
+
+// run Marching Tetrahedra algorithm, streaming the grid one x slice at a time so that
+// every sample is evaluated once and only the edge vertices of two slices are kept
+pub fn marching_tetrahedra(
+    range: f32,
+    split: usize,
+    func: &dyn Fn(&Point3<f32>) -> f32,
+) -> Polygons {
     let grid_size = range * 2.0 / split as f32;
+    let n = split + 1;
+    let index = |y: usize, z: usize| y * n + z;
+    let point = |x: usize, y: usize, z: usize| Point3 {
+        x: x as f32 * grid_size - range,
+        y: y as f32 * grid_size - range,
+        z: z as f32 * grid_size - range,
+    };
+    let sample_slice = |x: usize| {
+        (0..n * n)
+            .map(|i| func(&point(x, i / n, i % n)))
+            .collect::<Vec<_>>()
+    };
 
     let mut vertices = Vec::new();
 
@@ -66,43 +169,33 @@ fn make_model_implicit(range: f32, split: usize, func: fn(&Point3<f32>) -> f32)
         (1, 0, 1), // XZ
         (1, 1, 1), // XYZ
     ];
-    let verts = (0..=split)
-        .map(|x| {
-            (0..=split)
-                .map(|y| {
-                    (0..=split)
-                        .map(|z| {
-                            EDGE_DIRECTIONS
-                                .iter()
-                                .map(|(dx, dy, dz)| {
-                                    let p0 = Point3 {
-                                        x: x as f32 * grid_size - range,
-                                        y: y as f32 * grid_size - range,
-                                        z: z as f32 * grid_size - range,
-                                    };
-                                    let p1 = Point3 {
-                                        x: (x + dx) as f32 * grid_size - range,
-                                        y: (y + dy) as f32 * grid_size - range,
-                                        z: (z + dz) as f32 * grid_size - range,
-                                    };
-                                    let v0 = func(&p0);
-                                    let v1 = func(&p1);
-                                    if v0 * v1 < 0.0 {
-                                        let t = v0 / (v0 - v1);
-                                        let id = vertices.len();
-                                        vertices.push(p0 + (p1 - p0) * t);
-                                        Some(id)
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect::<Vec<_>>()
-                        })
-                        .collect::<Vec<_>>()
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect::<Vec<_>>();
+    // the edges within a slice, and those reaching into the next one
+    const IN_SLICE: [usize; 3] = [1, 2, 4];
+    const ACROSS: [usize; 4] = [0, 3, 5, 6];
+
+    // the vertex on each edge of the given directions from the samples of slice `s0`,
+    // into `edges` at [index(y, z) * 7 + direction], usize::MAX where it is not crossed
+    let mut find_edges =
+        |x: usize, s0: &[f32], s1: &[f32], directions: &[usize], edges: &mut [usize]| {
+            for y in 0..n {
+                for z in 0..n {
+                    for &d in directions {
+                        let (dx, dy, dz) = EDGE_DIRECTIONS[d];
+                        if y + dy > split || z + dz > split {
+                            continue;
+                        }
+                        let v0 = s0[index(y, z)];
+                        let v1 = if dx == 0 { s0 } else { s1 }[index(y + dy, z + dz)];
+                        if v0 * v1 < 0.0 {
+                            let p0 = point(x, y, z);
+                            let p1 = point(x + dx, y + dy, z + dz);
+                            edges[index(y, z) * 7 + d] = vertices.len();
+                            vertices.push(p0 + (p1 - p0) * (v0 / (v0 - v1)));
+                        }
+                    }
+                }
+            }
+        };
 
     // add faces
     const GRID_FACES: [[usize; 3]; 18] = [
@@ -137,51 +230,80 @@ fn make_model_implicit(range: f32, split: usize, func: fn(&Point3<f32>) -> f32)
 
     let mut faces = Vec::new();
 
+    let mut samples = sample_slice(0);
+    let mut slice_edges = vec![usize::MAX; n * n * 7];
+    let mut next_slice_edges = vec![usize::MAX; n * n * 7];
+    find_edges(0, &samples, &samples, &IN_SLICE, &mut slice_edges);
+
     for x in 0..split {
+        let next_samples = sample_slice(x + 1);
+        find_edges(
+            x + 1,
+            &next_samples,
+            &next_samples,
+            &IN_SLICE,
+            &mut next_slice_edges,
+        );
+        find_edges(x, &samples, &next_samples, &ACROSS, &mut slice_edges);
+
         for y in 0..split {
             for z in 0..split {
-                let mut grid_edges = verts[x][y][z].clone();
-                grid_edges.extend([
+                let edge = |slice: &[usize], y: usize, z: usize, d: usize| {
+                    Some(slice[index(y, z) * 7 + d]).filter(|&v| v != usize::MAX)
+                };
+                let (e, ne) = (&slice_edges[..], &next_slice_edges[..]);
+                let grid_edges = [
+                    edge(e, y, z, 0),
+                    edge(e, y, z, 1),
+                    edge(e, y, z, 2),
+                    edge(e, y, z, 3),
+                    edge(e, y, z, 4),
+                    edge(e, y, z, 5),
+                    edge(e, y, z, 6),
                     // X
-                    verts[x + 1][y][z][1], // Y
-                    verts[x + 1][y][z][2], // Z
-                    verts[x + 1][y][z][4], // YZ
+                    edge(ne, y, z, 1), // Y
+                    edge(ne, y, z, 2), // Z
+                    edge(ne, y, z, 4), // YZ
                     // Y
-                    verts[x][y + 1][z][0], // X
-                    verts[x][y + 1][z][2], // Z
-                    verts[x][y + 1][z][5], // XZ
+                    edge(e, y + 1, z, 0), // X
+                    edge(e, y + 1, z, 2), // Z
+                    edge(e, y + 1, z, 5), // XZ
                     // Z
-                    verts[x][y][z + 1][0],     // X
-                    verts[x][y][z + 1][1],     // Y
-                    verts[x][y][z + 1][3],     // XY
-                    verts[x + 1][y + 1][z][2], // XY -> Z
-                    verts[x][y + 1][z + 1][0], // YZ -> X
-                    verts[x + 1][y][z + 1][1], // XZ -> Y
-                ]);
-
-                let edges = GRID_FACES
-                    .iter()
-                    .map(|i| {
-                        let v = i.iter().filter_map(|&i| grid_edges[i]).collect::<Vec<_>>();
-                        // assert_eq!(v.len() % 2, 0);
-                        if v.len() == 2 {
-                            Some((v[0], v[1]))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect::<Vec<_>>();
+                    edge(e, y, z + 1, 0),     // X
+                    edge(e, y, z + 1, 1),     // Y
+                    edge(e, y, z + 1, 3),     // XY
+                    edge(ne, y + 1, z, 2),    // XY -> Z
+                    edge(e, y + 1, z + 1, 0), // YZ -> X
+                    edge(ne, y, z + 1, 1),    // XZ -> Y
+                ];
+                if grid_edges.iter().all(Option::is_none) {
+                    continue;
+                }
+
+                let edges = GRID_FACES.map(|i| {
+                    let mut v = i.iter().filter_map(|&i| grid_edges[i]);
+                    match (v.next(), v.next(), v.next()) {
+                        (Some(a), Some(b), None) => Some((a, b)),
+                        _ => None,
+                    }
+                });
 
                 for i in GRID_BODIES {
                     let mut f = i.iter().filter_map(|&i| edges[i]).collect::<Vec<_>>();
-                    if f.is_empty() { continue; }
+                    if f.is_empty() {
+                        continue;
+                    }
                     // assert!(f.len() != 1 && f.len() != 2);
-                    if f.len() < 3 { continue; }
+                    if f.len() < 3 {
+                        continue;
+                    }
                     let e0 = f.pop().unwrap();
                     let (mut pr, mut cr) = e0;
                     let mut e = vec![pr, cr];
                     for _ in 0..f.len() {
-                        if cr == e0.0 { break; }
+                        if cr == e0.0 {
+                            break;
+                        }
                         for ff in f.iter() {
                             if ff.0 == cr && ff.1 != pr {
                                 e.push(ff.1);
@@ -202,10 +324,7 @@ fn make_model_implicit(range: f32, split: usize, func: fn(&Point3<f32>) -> f32)
                     }
 
                     if e.len() == 4 {
-                        e = vec![
-                            e[0], e[1], e[3],
-                            e[1], e[2], e[3],
-                        ];
+                        e = vec![e[0], e[1], e[3], e[1], e[2], e[3]];
                     }
                     for es in e.chunks(3) {
                         faces.push([es[0], es[1], es[2]]);
@@ -213,18 +332,21 @@ fn make_model_implicit(range: f32, split: usize, func: fn(&Point3<f32>) -> f32)
                 }
             }
         }
+
+        samples = next_samples;
+        std::mem::swap(&mut slice_edges, &mut next_slice_edges);
+        next_slice_edges.fill(usize::MAX);
     }
 
-    HalfEdgeMesh::from_face_vertex_mesh(&vertices, &faces)
+    (vertices, faces)
 }
 
-static mut GLOBAL_SCENE: Option<Rc<RefCell<Scene>>> = None;
-
-fn get_scene() -> &'static mut Rc<RefCell<Scene>> {
-    unsafe { GLOBAL_SCENE.as_mut().unwrap() }
-}
+common::scene_slot!(Scene);
 
-const MAX_POINTS: usize = 1024;
+// vertices are indexed by u16
+const MAX_POINTS: usize = 65536;
+// a closed surface has about twice as many triangles as vertices
+const MAX_TRIANGLES: usize = MAX_POINTS * 2;
 impl Scene {
     fn new(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
         canvas.set_width(CANVAS_SIZE);
@@ -240,34 +362,48 @@ impl Scene {
             include_str!("shader/vertex.glsl"),
             include_str!("shader/fragment.glsl"),
         )?;
+        let program_lit = common::create_program(
+            &gl,
+            include_str!("shader/vertex_lit.glsl"),
+            include_str!("shader/fragment_lit.glsl"),
+        )?;
 
         gl.enable(GL::DEPTH_TEST);
         gl.depth_func(GL::LEQUAL);
         // gl.enable(GL::CULL_FACE);
 
-        let vao_lin = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 2)?;
-        let vao_tri = MyVAO::new(gl.clone(), MAX_POINTS, MAX_POINTS * 3)?;
+        // both half-edges of every edge are drawn
+        let vao_lin = MyVAO::new(gl.clone(), MAX_POINTS, MAX_TRIANGLES * 6)?;
+        let vao_tri = MyVAO::new_with_normal(gl.clone(), MAX_POINTS, MAX_TRIANGLES * 3)?;
 
         let mvp_location = gl
             .get_uniform_location(&program, "mvpMatrix")
             .ok_or("Failed to get uniform location")?;
+        let mvp_location_lit = gl
+            .get_uniform_location(&program_lit, "mvpMatrix")
+            .ok_or("Failed to get uniform location")?;
 
         let mut r = Self {
             gl,
             program,
+            program_lit,
             vao_lin,
             vao_tri,
 
             mvp_location,
+            mvp_location_lit,
 
-            camera_dist: 5.0,
-            camera_pitch: 0.0,
-            camera_yaw: 0.0,
+            camera: OrbitCamera::new(5.0),
 
             mesh: HalfEdgeMesh::empty(),
             // mesh: make_model_implicit(1., 10, |p| {
             //     p.to_vec().magnitude() - 0.5
             // }),
+            function: expr::compile(DEFAULT_FUNCTION)?,
+            split: 10,
+            method: Method::Tetrahedra,
+            stats: MeshStats::default(),
+            normals: std::collections::HashMap::new(),
         };
 
         r.update();
@@ -276,22 +412,79 @@ impl Scene {
     }
 
     fn update_mesh(&mut self, split: usize) {
-        self.mesh = make_model_implicit(1., split, |p| {
-            (Point3::from_vec(Vector3::new(p.x, 0.0, p.z).normalize_to(0.5)) - p).magnitude() - 0.2
-        });
+        self.split = split;
+        let start = js_sys::Date::now();
+        let polygons = make_model_implicit(1., split, &self.function, self.method);
+        let drawable = polygons.0.len() <= MAX_POINTS && polygons.1.len() <= MAX_TRIANGLES;
+        self.stats = MeshStats {
+            time_ms: js_sys::Date::now() - start,
+            drawable,
+            ..measure(&polygons)
+        };
+        self.mesh = if drawable {
+            HalfEdgeMesh::from_face_vertex_mesh(&polygons.0, &polygons.1)
+        } else {
+            HalfEdgeMesh::empty()
+        };
+
+        // smooth shading from the field, a small step against the grid spacing.
+        // computed here rather than in `update`, which also runs on every camera move
+        let h = 1e-2 / split as f32;
+        self.normals = self
+            .mesh
+            .vertices
+            .iter()
+            .map(|(id, vert)| {
+                let normal = gradient(&self.function, &vert.borrow().pos, h);
+                let normal = if normal.magnitude2() > 0.0 {
+                    normal.normalize()
+                } else {
+                    Vector3::unit_y()
+                };
+                (*id, normal)
+            })
+            .collect();
         self.update();
     }
 
+    fn set_function(&mut self, src: &str) -> Result<(), String> {
+        self.function = expr::compile(src)?;
+        self.update_mesh(self.split);
+        Ok(())
+    }
+
+    fn set_method(&mut self, name: &str) -> Result<(), String> {
+        self.method = match name {
+            "tetrahedra" => Method::Tetrahedra,
+            "cubes" => Method::Cubes,
+            "dual" => Method::DualContouring,
+            "octree" => Method::Octree,
+            _ => return Err(format!("unknown method '{}'", name)),
+        };
+        self.update_mesh(self.split);
+        Ok(())
+    }
+
+    fn set_model(&mut self, name: &str) -> Result<(), String> {
+        let model = sdf::preset(name).ok_or(format!("unknown model '{}'", name))?;
+        self.function = Box::new(move |p| model.eval(p));
+        self.update_mesh(self.split);
+        Ok(())
+    }
+
     fn update(&mut self) {
         let mut verts = std::collections::HashMap::<u32, u16>::new();
 
         let mut v = Vec::new();
+        let mut n = Vec::new();
 
         for (i, (id, vert)) in self.mesh.vertices.iter().enumerate() {
             let vert = vert.borrow();
             v.push(vert.pos.x);
             v.push(vert.pos.y);
             v.push(vert.pos.z);
+            let normal = self.normals[id];
+            n.extend([normal.x, normal.y, normal.z]);
             verts.insert(*id, i as u16);
         }
 
@@ -339,38 +532,28 @@ impl Scene {
             .collect::<Vec<u16>>();
 
         self.vao_tri.send_data(&v, &c_f, &i_f);
+        self.vao_tri.send_normal_data(&n);
         self.vao_lin.send_data(&v, &c_e, &i_e);
     }
 
     fn draw(&self) {
-        self.gl.use_program(Some(&self.program));
-        send_mvp_matrix(
-            &self.gl,
-            &self.mvp_location,
-            self.camera_pitch,
-            self.camera_yaw,
-            self.camera_dist,
-        );
-
         self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
         self.gl.clear_depth(1.0);
         self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
 
+        self.gl.use_program(Some(&self.program_lit));
+        self.camera
+            .send_mvp_matrix(&self.gl, &self.mvp_location_lit);
         self.vao_tri.draw_elements(GL::TRIANGLES);
+
+        self.gl.use_program(Some(&self.program));
+        self.camera.send_mvp_matrix(&self.gl, &self.mvp_location);
         self.vao_lin.draw_elements(GL::LINES);
         self.gl.flush();
     }
 
     fn move_camera(&mut self, pan: (f32, f32), zoom: f32) {
-        self.camera_yaw -= pan.0 * 0.01;
-        self.camera_pitch -= pan.1 * 0.01;
-
-        self.camera_pitch = self
-            .camera_pitch
-            .clamp(-consts::FRAC_PI_2 + 1e-6, consts::FRAC_PI_2 - 1e-6);
-
-        self.camera_dist *= 2.0f32.powf(zoom);
-        self.camera_dist = self.camera_dist.clamp(1., 100.);
+        self.camera.move_camera(pan, zoom);
 
         self.update();
     }
@@ -383,66 +566,46 @@ pub fn start(canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
     console_error_panic_hook::set_once();
 
     let scene = Rc::new(RefCell::new(Scene::new(canvas)?));
-
-    unsafe {
-        GLOBAL_SCENE = Some(scene);
-    }
+    set_scene(scene);
 
     Ok(())
 }
 
 #[wasm_bindgen]
 pub fn draw() -> Result<(), JsValue> {
-    let scene = get_scene();
-    scene.borrow().draw();
-    Ok(())
+    with_scene(|scene| scene.draw())
 }
 
 #[wasm_bindgen]
 pub fn pan(dx: f32, dy: f32, zoom: f32) -> Result<(), JsValue> {
-    let scene = get_scene();
-    scene.borrow_mut().move_camera((dx, dy), zoom);
-    Ok(())
+    with_scene(|scene| scene.move_camera((dx, dy), zoom))
 }
 
 #[wasm_bindgen]
 pub fn update_spl(split: usize) -> Result<(), JsValue> {
-    let scene = get_scene();
-    scene.borrow_mut().update_mesh(split);
-    Ok(())
+    with_scene(|scene| scene.update_mesh(split))
 }
 
-fn send_mvp_matrix(
-    gl: &GL,
-    location: &WebGlUniformLocation,
-    camera_pitch: f32,
-    camera_yaw: f32,
-    camera_dist: f32,
-) {
-    let eye = glm::quat_rotate_vec3(
-        &(glm::quat_angle_axis(camera_yaw, &glm::Vec3::new(0.0, 1.0, 0.0))
-            * glm::quat_angle_axis(camera_pitch, &glm::Vec3::new(1.0, 0.0, 0.0))),
-        &glm::Vec3::new(0.0, 0.0, camera_dist),
-    );
-    let center = glm::Vec3::new(0.0, 0.0, 0.0);
-    let up = glm::Vec3::new(0.0, 1.0, 0.0);
-    let view_matrix = glm::look_at(&eye, &center, &up);
-
-    let aspect = 1.0;
-    let fovy = 45.0 * consts::PI / 180.0;
-    let near = 0.1;
-    let far = 100.0;
-    let projection_matrix = glm::perspective(aspect, fovy, near, far);
-
-    let mvp_matrix = projection_matrix * view_matrix;
-    let mvp_arrays: [[f32; 4]; 4] = mvp_matrix.into();
-    let mvp_matrices = mvp_arrays.iter().flat_map(|a| *a).collect::<Vec<_>>();
-
-    gl.uniform_matrix4fv_with_f32_array_and_src_offset_and_src_length(
-        Some(location),
-        false,
-        &mvp_matrices,
-        0,
-        0,
-    );
+// errors are reported as "column N: message"
+#[wasm_bindgen]
+pub fn set_function(src: &str) -> Result<(), JsValue> {
+    with_scene(|scene| scene.set_function(src))?.map_err(JsValue::from)
+}
+
+// one of the SDF models in `sdf::preset`
+#[wasm_bindgen]
+pub fn set_model(name: &str) -> Result<(), JsValue> {
+    with_scene(|scene| scene.set_model(name))?.map_err(JsValue::from)
+}
+
+// "tetrahedra", "cubes", "dual" or "octree"
+#[wasm_bindgen]
+pub fn set_method(name: &str) -> Result<(), JsValue> {
+    with_scene(|scene| scene.set_method(name))?.map_err(JsValue::from)
+}
+
+// size, quality and timing of the current mesh
+#[wasm_bindgen]
+pub fn mesh_stats() -> Result<MeshStats, JsValue> {
+    with_scene(|scene| scene.stats)
 }
@@ -0,0 +1,217 @@
+use cgmath::Point3;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::Polygons;
+
+// corner offsets, in the usual order: the bottom face counterclockwise, then the top one
+const CORNERS: [(usize, usize, usize); 8] = [
+    (0, 0, 0),
+    (1, 0, 0),
+    (1, 1, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (1, 0, 1),
+    (1, 1, 1),
+    (0, 1, 1),
+];
+
+const EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (1, 2),
+    (2, 3),
+    (3, 0),
+    (4, 5),
+    (5, 6),
+    (6, 7),
+    (7, 4),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+// corner cycles of the faces, counterclockwise seen from outside the cube
+const FACES: [[usize; 4]; 6] = [
+    [0, 3, 2, 1],
+    [4, 5, 6, 7],
+    [0, 1, 5, 4],
+    [2, 3, 7, 6],
+    [0, 4, 7, 3],
+    [1, 2, 6, 5],
+];
+
+fn edge_between(a: usize, b: usize) -> usize {
+    EDGES
+        .iter()
+        .position(|&(p, q)| (p, q) == (a, b) || (p, q) == (b, a))
+        .unwrap()
+}
+
+// the isoline segments on one face as (from, to) edges. the segments cut off the runs of
+// positive corners, or the negative corners when they are not connected across the face.
+// every crossed edge is left by one face and entered by the other, so they chain into loops
+fn face_segments(face: &[usize; 4], mask: u8, connected: bool, out: &mut Vec<(usize, usize)>) {
+    let positive = |i: usize| mask & (1 << face[i % 4]) == 0;
+    let edge = |i: usize| edge_between(face[i % 4], face[(i + 1) % 4]);
+    let crossings = (0..4).filter(|&i| positive(i) != positive(i + 1)).count();
+    if crossings == 4 && !connected {
+        for i in (0..4).filter(|&i| !positive(i)) {
+            out.push((edge(i), edge(i + 3)));
+        }
+        return;
+    }
+    // each positive run from p to q, entered after p-1 and left before q+1
+    for p in (0..4).filter(|&p| positive(p) && !positive(p + 3)) {
+        let mut q = p;
+        while positive(q + 1) {
+            q += 1;
+        }
+        out.push((edge(p + 3), edge(q)));
+    }
+}
+
+// triangles over the crossed edges, as fans over the loops of face segments
+fn triangulate(mask: u8, connected: [bool; 6]) -> Vec<[u8; 3]> {
+    let mut segments = vec![];
+    for (face, &c) in FACES.iter().zip(&connected) {
+        face_segments(face, mask, c, &mut segments);
+    }
+    let mut next = [usize::MAX; 12];
+    for &(a, b) in &segments {
+        next[a] = b;
+    }
+
+    let mut triangles = vec![];
+    let mut visited = [false; 12];
+    for start in 0..12 {
+        if next[start] == usize::MAX || visited[start] {
+            continue;
+        }
+        let mut ring = vec![];
+        let mut e = start;
+        while !visited[e] {
+            visited[e] = true;
+            ring.push(e as u8);
+            e = next[e];
+        }
+        // wound counterclockwise seen from the positive side
+        for k in 1..ring.len().saturating_sub(1) {
+            triangles.push([ring[0], ring[k + 1], ring[k]]);
+        }
+    }
+    triangles
+}
+
+// the 256 cases, for faces whose ambiguity is resolved the usual way (negative corners apart)
+fn case_table() -> &'static Vec<Vec<[u8; 3]>> {
+    static TABLE: OnceLock<Vec<Vec<[u8; 3]>>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        (0..=255)
+            .map(|mask| triangulate(mask, [false; 6]))
+            .collect()
+    })
+}
+
+// asymptotic decider: whether the negative corners of an ambiguous face are joined inside it,
+// from the sign of the bilinear interpolant at its saddle point
+fn negatives_connected(v: [f32; 4]) -> bool {
+    let denominator = v[0] + v[2] - v[1] - v[3];
+    if denominator == 0.0 {
+        return false;
+    }
+    (v[0] * v[2] - v[1] * v[3]) / denominator < 0.0
+}
+
+// marching cubes over the (split+1)^3 samples in [-range, range]^3, negative inside
+pub fn polygonize(range: f32, split: usize, func: &dyn Fn(&Point3<f32>) -> f32) -> Polygons {
+    let grid_size = range * 2.0 / split as f32;
+    let n = split + 1;
+    let index = |x: usize, y: usize, z: usize| (x * n + y) * n + z;
+    let point = |x: usize, y: usize, z: usize| {
+        Point3::new(
+            x as f32 * grid_size - range,
+            y as f32 * grid_size - range,
+            z as f32 * grid_size - range,
+        )
+    };
+
+    let mut samples = vec![0.0; n * n * n];
+    for x in 0..n {
+        for y in 0..n {
+            for z in 0..n {
+                samples[index(x, y, z)] = func(&point(x, y, z));
+            }
+        }
+    }
+
+    let table = case_table();
+    let mut vertices = vec![];
+    let mut faces = vec![];
+    // vertex on the grid edge from a sample toward +x, +y or +z
+    let mut edge_vertices = HashMap::<(usize, usize), usize>::new();
+
+    for x in 0..split {
+        for y in 0..split {
+            for z in 0..split {
+                let corner = |i: usize| {
+                    let (dx, dy, dz) = CORNERS[i];
+                    (x + dx, y + dy, z + dz)
+                };
+                let values: [f32; 8] = std::array::from_fn(|i| {
+                    let (x, y, z) = corner(i);
+                    samples[index(x, y, z)]
+                });
+                let mask = (0..8)
+                    .filter(|&i| values[i] < 0.0)
+                    .fold(0u8, |m, i| m | (1 << i));
+                if mask == 0 || mask == 255 {
+                    continue;
+                }
+
+                let ambiguous = FACES.map(|f| {
+                    let s = f.map(|i| values[i] < 0.0);
+                    s[0] == s[2] && s[1] == s[3] && s[0] != s[1]
+                });
+                let computed;
+                let triangles = if ambiguous.contains(&true) {
+                    computed = triangulate(
+                        mask,
+                        FACES.map(|f| negatives_connected(f.map(|i| values[i]))),
+                    );
+                    &computed
+                } else {
+                    &table[mask as usize]
+                };
+
+                let mut vertex = |e: u8| {
+                    let (a, b) = EDGES[e as usize];
+                    let (pa, pb) = (corner(a), corner(b));
+                    let lower = pa.min(pb);
+                    let axis = if pa.0 != pb.0 {
+                        0
+                    } else if pa.1 != pb.1 {
+                        1
+                    } else {
+                        2
+                    };
+                    *edge_vertices
+                        .entry((index(lower.0, lower.1, lower.2), axis))
+                        .or_insert_with(|| {
+                            let (va, vb) = (values[a], values[b]);
+                            let t = va / (va - vb);
+                            let p0 = point(pa.0, pa.1, pa.2);
+                            let p1 = point(pb.0, pb.1, pb.2);
+                            vertices.push(p0 + (p1 - p0) * t);
+                            vertices.len() - 1
+                        })
+                };
+                for t in triangles {
+                    faces.push(t.map(&mut vertex));
+                }
+            }
+        }
+    }
+
+    (vertices, faces)
+}
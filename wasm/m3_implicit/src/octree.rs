@@ -0,0 +1,403 @@
+use cgmath::prelude::*;
+use cgmath::{Point3, Vector3};
+use std::collections::{HashMap, HashSet};
+
+use crate::Polygons;
+
+// cells are always split down to this depth, so that small isolated parts are not missed
+const MIN_DEPTH: u32 = 3;
+// a cell is split if the surface may pass through it: its corners and center differ in sign,
+// or |f(center)| is within this factor of its half diagonal. 1 is enough for exact distance
+// fields, other functions need some slack and may still lose features thinner than a cell
+const DISTANCE_BOUND: f32 = 1.5;
+// and if the function differs from the trilinear interpolation of its corners by more than
+// this fraction of the finest cell, so that flat parts of the surface stay coarse
+const ERROR_TOLERANCE: f32 = 0.05;
+
+// positions are on an integer lattice, a cell of the finest level is 2 units wide
+// so that the centers of cells and faces are lattice points as well
+type Node = [u32; 3];
+
+// corner `k` of the cube at `origin`, in x, y, z bit order
+fn corner(origin: Node, size: u32, k: usize) -> Node {
+    let mut p = origin;
+    for (axis, c) in p.iter_mut().enumerate() {
+        *c += ((k >> axis) & 1) as u32 * size;
+    }
+    p
+}
+
+struct Cell {
+    origin: Node,
+    size: u32,
+    // index of the first of 8 children, in x, y, z bit order
+    children: Option<usize>,
+}
+
+struct Octree {
+    cells: Vec<Cell>,
+    resolution: u32,
+}
+
+impl Octree {
+    fn split(&mut self, i: usize) {
+        let (origin, half) = (self.cells[i].origin, self.cells[i].size / 2);
+        self.cells[i].children = Some(self.cells.len());
+        for k in 0..8 {
+            self.cells.push(Cell {
+                origin: corner(origin, half, k),
+                size: half,
+                children: None,
+            });
+        }
+    }
+
+    fn find_leaf(&self, p: &Node) -> usize {
+        let mut i = 0;
+        while let Some(c) = self.cells[i].children {
+            let cell = &self.cells[i];
+            let half = cell.size / 2;
+            i = c
+                + (0..3)
+                    .filter(|&axis| p[axis] >= cell.origin[axis] + half)
+                    .map(|axis| 1 << axis)
+                    .sum::<usize>();
+        }
+        i
+    }
+
+    fn leaves(&self) -> Vec<usize> {
+        (0..self.cells.len())
+            .filter(|&i| self.cells[i].children.is_none())
+            .collect()
+    }
+
+    // split until leaves that touch, even at a corner, differ by at most one level
+    fn balance(&mut self) {
+        loop {
+            let mut changed = false;
+            for i in self.leaves() {
+                let (origin, size) = (self.cells[i].origin, self.cells[i].size);
+                for d in 0..27 {
+                    let dir = [d % 3, d / 3 % 3, d / 9].map(|k: i32| k - 1);
+                    if dir == [0, 0, 0] {
+                        continue;
+                    }
+                    // a lattice point just outside this leaf in that direction
+                    let mut p = origin;
+                    let mut outside = false;
+                    for axis in 0..3 {
+                        match dir[axis] {
+                            -1 if origin[axis] == 0 => outside = true,
+                            -1 => p[axis] -= 1,
+                            0 => p[axis] += size / 2,
+                            _ if origin[axis] + size >= self.resolution => outside = true,
+                            _ => p[axis] += size,
+                        }
+                    }
+                    if outside {
+                        continue;
+                    }
+                    let j = self.find_leaf(&p);
+                    if self.cells[j].size > size * 2 {
+                        self.split(j);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+}
+
+struct Mesher<'a> {
+    func: &'a dyn Fn(&Point3<f32>) -> f32,
+    range: f32,
+    unit: f32,
+    // corners of the leaves, the only points where faces and edges are split
+    nodes: HashSet<Node>,
+    values: HashMap<Node, f32>,
+    edge_vertices: HashMap<(Node, Node), usize>,
+    vertices: Vec<Point3<f32>>,
+    faces: Vec<[usize; 3]>,
+}
+
+impl Mesher<'_> {
+    fn point(&self, p: &Node) -> Point3<f32> {
+        Point3::new(
+            p[0] as f32 * self.unit - self.range,
+            p[1] as f32 * self.unit - self.range,
+            p[2] as f32 * self.unit - self.range,
+        )
+    }
+
+    fn value(&mut self, p: &Node) -> f32 {
+        if let Some(&v) = self.values.get(p) {
+            return v;
+        }
+        let v = (self.func)(&self.point(p));
+        self.values.insert(*p, v);
+        v
+    }
+
+    // whether the surface may cross the cell and is not yet approximated within `tolerance`,
+    // checked at the centers of the cell, its faces and its edges
+    fn needs_split(&mut self, origin: Node, size: u32, tolerance: f32) -> bool {
+        let h = size / 2;
+        let center = origin.map(|c| c + h);
+        let half_diagonal = h as f32 * self.unit * 3f32.sqrt();
+        let corners: [f32; 8] = std::array::from_fn(|k| self.value(&corner(origin, size, k)));
+        let v = self.value(&center);
+        // the bound only holds for distance fields, a sign change shows the surface regardless
+        let crossed = corners.iter().any(|&c| (c < 0.0) != (v < 0.0));
+        if !crossed && v.abs() > DISTANCE_BOUND * half_diagonal {
+            return false;
+        }
+        for m in 0..27 {
+            let t = [m % 3, m / 3 % 3, m / 9];
+            if !t.contains(&1) {
+                continue;
+            }
+            let trilinear = (0..8)
+                .map(|k| {
+                    let weight = (0..3)
+                        .map(|axis| match (t[axis], (k >> axis) & 1) {
+                            (1, _) => 0.5,
+                            (a, b) if a == 2 * b => 1.0,
+                            _ => 0.0,
+                        })
+                        .product::<f32>();
+                    weight * corners[k]
+                })
+                .sum::<f32>();
+            let mut p = origin;
+            for axis in 0..3 {
+                p[axis] += t[axis] as u32 * h;
+            }
+            if (self.value(&p) - trilinear).abs() > tolerance {
+                return true;
+            }
+        }
+        false
+    }
+
+    // triangles covering the square face at `o` spanned by `size` along axes `u` and `v`.
+    // it only depends on the nodes on the face, so the leaves on both sides agree
+    fn face(&self, o: Node, u: usize, v: usize, size: u32, out: &mut Vec<[Node; 3]>) {
+        let at = |i: u32, j: u32| {
+            let mut p = o;
+            p[u] += i;
+            p[v] += j;
+            p
+        };
+        let (h, s) = (size / 2, size);
+        let center = at(h, h);
+        if self.nodes.contains(&center) {
+            // finer cells on the other side, follow their faces
+            for (i, j) in [(0, 0), (h, 0), (0, h), (h, h)] {
+                self.face(at(i, j), u, v, h, out);
+            }
+            return;
+        }
+        let boundary = [
+            (at(0, 0), true),
+            (at(h, 0), false),
+            (at(s, 0), true),
+            (at(s, h), false),
+            (at(s, s), true),
+            (at(h, s), false),
+            (at(0, s), true),
+            (at(0, h), false),
+        ]
+        .into_iter()
+        .filter(|(p, corner)| *corner || self.nodes.contains(p))
+        .map(|(p, _)| p)
+        .collect::<Vec<_>>();
+        if boundary.len() == 4 {
+            // a plain square, split along the same diagonal seen from either side
+            out.push([boundary[0], boundary[1], boundary[2]]);
+            out.push([boundary[0], boundary[2], boundary[3]]);
+            return;
+        }
+        for k in 0..boundary.len() {
+            out.push([center, boundary[k], boundary[(k + 1) % boundary.len()]]);
+        }
+    }
+
+    fn vertex(&mut self, a: Node, b: Node) -> usize {
+        let key = if a < b { (a, b) } else { (b, a) };
+        if let Some(&i) = self.edge_vertices.get(&key) {
+            return i;
+        }
+        let (va, vb) = (self.value(&key.0), self.value(&key.1));
+        let (pa, pb) = (self.point(&key.0), self.point(&key.1));
+        self.vertices.push(pa + (pb - pa) * (va / (va - vb)));
+        self.edge_vertices.insert(key, self.vertices.len() - 1);
+        self.vertices.len() - 1
+    }
+
+    // a triangle over three crossed edges, oriented by the midpoints of the edges since
+    // the vertices themselves coincide where the function is zero at a node
+    fn triangle(&mut self, edges: [(Node, Node); 3], outward: Vector3<f32>) {
+        let m = edges.map(|(a, b)| self.point(&a).midpoint(self.point(&b)));
+        let t = edges.map(|(a, b)| self.vertex(a, b));
+        let n = (m[1] - m[0]).cross(m[2] - m[0]);
+        self.faces.push(if n.dot(outward) >= 0.0 {
+            t
+        } else {
+            [t[0], t[2], t[1]]
+        });
+    }
+
+    fn tetrahedron(&mut self, tet: [Node; 4]) {
+        let values = tet.map(|p| self.value(&p));
+        let (neg, pos): (Vec<usize>, Vec<usize>) = (0..4).partition(|&k| values[k] < 0.0);
+        if neg.is_empty() || pos.is_empty() {
+            return;
+        }
+        // wound counterclockwise seen from the positive side
+        let centroid = |ks: &[usize]| {
+            ks.iter()
+                .map(|&k| self.point(&tet[k]).to_vec())
+                .sum::<Vector3<f32>>()
+                / ks.len() as f32
+        };
+        let outward = centroid(&pos) - centroid(&neg);
+
+        match (neg.len(), pos.len()) {
+            (1, _) | (_, 1) => {
+                let (lone, others) = if neg.len() == 1 {
+                    (neg[0], pos)
+                } else {
+                    (pos[0], neg)
+                };
+                self.triangle([0, 1, 2].map(|k| (tet[lone], tet[others[k]])), outward);
+            }
+            _ => {
+                let q = [(0, 0), (0, 1), (1, 1), (1, 0)].map(|(i, j)| (tet[neg[i]], tet[pos[j]]));
+                self.triangle([q[0], q[1], q[2]], outward);
+                self.triangle([q[0], q[2], q[3]], outward);
+            }
+        }
+    }
+
+    // cut every leaf into tetrahedra over the triangles of its faces
+    fn mesh(mut self, tree: &Octree) -> Polygons {
+        let leaves = tree.leaves();
+        for &i in &leaves {
+            let cell = &tree.cells[i];
+            self.nodes
+                .extend((0..8).map(|k| corner(cell.origin, cell.size, k)));
+        }
+
+        let mut triangles = vec![];
+        for &i in &leaves {
+            let cell = &tree.cells[i];
+            let s = cell.size;
+            let center = cell.origin.map(|c| c + s / 2);
+            triangles.clear();
+            for axis in 0..3 {
+                for side in [0, s] {
+                    let mut o = cell.origin;
+                    o[axis] += side;
+                    self.face(o, (axis + 1) % 3, (axis + 2) % 3, s, &mut triangles);
+                }
+            }
+            for t in &triangles {
+                self.tetrahedron([center, t[0], t[1], t[2]]);
+            }
+        }
+
+        (self.vertices, self.faces)
+    }
+}
+
+// adaptive marching tetrahedra in [-range, range]^3, negative inside. cells are refined
+// near the surface down to the size of a `split` grid, and each leaf is cut into tetrahedra
+// that match its neighbors' faces, so that the levels meet without cracks
+pub fn polygonize(range: f32, split: usize, func: &dyn Fn(&Point3<f32>) -> f32) -> Polygons {
+    let (mesher, tree) = refine(range, split, func);
+    mesher.mesh(&tree)
+}
+
+// the balanced octree around the surface, with the mesher that sampled it
+fn refine(range: f32, split: usize, func: &dyn Fn(&Point3<f32>) -> f32) -> (Mesher<'_>, Octree) {
+    let max_depth = ((split.max(2) as f32).log2().ceil() as u32).max(MIN_DEPTH);
+    let resolution = 2 << max_depth;
+    let unit = range * 2.0 / resolution as f32;
+
+    let mut mesher = Mesher {
+        func,
+        range,
+        unit,
+        nodes: HashSet::new(),
+        values: HashMap::new(),
+        edge_vertices: HashMap::new(),
+        vertices: vec![],
+        faces: vec![],
+    };
+    let mut tree = Octree {
+        cells: vec![Cell {
+            origin: [0; 3],
+            size: resolution,
+            children: None,
+        }],
+        resolution,
+    };
+    let mut stack = vec![(0, 0)];
+    while let Some((i, depth)) = stack.pop() {
+        let (origin, size) = (tree.cells[i].origin, tree.cells[i].size);
+        if depth >= max_depth
+            || depth >= MIN_DEPTH && !mesher.needs_split(origin, size, ERROR_TOLERANCE * 2.0 * unit)
+        {
+            continue;
+        }
+        tree.split(i);
+        let c = tree.cells[i].children.unwrap();
+        stack.extend((c..c + 8).map(|j| (j, depth + 1)));
+    }
+    tree.balance();
+    (mesher, tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_is_watertight() {
+        let sphere = |p: &Point3<f32>| p.to_vec().magnitude() - 0.6;
+        let (mut mesher, tree) = refine(1.0, 64, &sphere);
+
+        // the surface crosses leaves of different sizes
+        let sizes = tree
+            .leaves()
+            .into_iter()
+            .map(|i| &tree.cells[i])
+            .filter(|c| {
+                let inside = (0..8)
+                    .filter(|&k| mesher.value(&corner(c.origin, c.size, k)) < 0.0)
+                    .count();
+                inside > 0 && inside < 8
+            })
+            .map(|c| c.size)
+            .collect::<HashSet<_>>();
+        assert!(sizes.len() > 1, "{:?}", sizes);
+
+        // every edge is shared by two faces, once in each direction
+        let (_, faces) = mesher.mesh(&tree);
+        assert!(!faces.is_empty());
+        let mut edges = HashMap::<(usize, usize), usize>::new();
+        for f in &faces {
+            for k in 0..3 {
+                *edges.entry((f[k], f[(k + 1) % 3])).or_default() += 1;
+            }
+        }
+        for (&(a, b), &n) in &edges {
+            assert_eq!(n, 1, "edge {} -> {}", a, b);
+            assert_eq!(edges.get(&(b, a)), Some(&1), "edge {} -> {}", b, a);
+        }
+    }
+}
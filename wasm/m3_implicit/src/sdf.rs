@@ -0,0 +1,247 @@
+use cgmath::prelude::*;
+use cgmath::{Point3, Quaternion, Rad, Vector2, Vector3};
+
+// signed distance fields composed as a tree, negative inside
+pub enum Sdf {
+    Sphere {
+        radius: f32,
+    },
+    // half extents
+    Box {
+        size: Vector3<f32>,
+    },
+    RoundBox {
+        size: Vector3<f32>,
+        radius: f32,
+    },
+    // around the y axis
+    Torus {
+        major: f32,
+        minor: f32,
+    },
+    Capsule {
+        a: Point3<f32>,
+        b: Point3<f32>,
+        radius: f32,
+    },
+    // around the y axis, `height` is the half height
+    Cylinder {
+        radius: f32,
+        height: f32,
+    },
+    // the inside is opposite to the unit `normal`
+    Plane {
+        normal: Vector3<f32>,
+        offset: f32,
+    },
+
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    // the first minus the second
+    Difference(Box<Sdf>, Box<Sdf>),
+    // the blends are rounded over a width of `k`
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    SmoothIntersection(Box<Sdf>, Box<Sdf>, f32),
+    SmoothDifference(Box<Sdf>, Box<Sdf>, f32),
+
+    Translate(Vector3<f32>, Box<Sdf>),
+    Rotate(Quaternion<f32>, Box<Sdf>),
+    // uniform, so that the result stays a distance
+    Scale(f32, Box<Sdf>),
+    // radians per unit along y
+    Twist(f32, Box<Sdf>),
+    // radians per unit along x, bending toward y
+    Bend(f32, Box<Sdf>),
+}
+
+// polynomial smooth minimum
+fn smin(a: f32, b: f32, k: f32) -> f32 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+fn box_distance(p: &Point3<f32>, size: &Vector3<f32>) -> f32 {
+    let q = Vector3::new(p.x.abs(), p.y.abs(), p.z.abs()) - size;
+    let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0)).magnitude();
+    outside + q.x.max(q.y).max(q.z).min(0.0)
+}
+
+impl Sdf {
+    pub fn sphere(radius: f32) -> Self {
+        Sdf::Sphere { radius }
+    }
+
+    pub fn cuboid(x: f32, y: f32, z: f32) -> Self {
+        Sdf::Box {
+            size: Vector3::new(x, y, z),
+        }
+    }
+
+    pub fn round_box(x: f32, y: f32, z: f32, radius: f32) -> Self {
+        Sdf::RoundBox {
+            size: Vector3::new(x, y, z),
+            radius,
+        }
+    }
+
+    pub fn torus(major: f32, minor: f32) -> Self {
+        Sdf::Torus { major, minor }
+    }
+
+    pub fn capsule(a: Point3<f32>, b: Point3<f32>, radius: f32) -> Self {
+        Sdf::Capsule { a, b, radius }
+    }
+
+    pub fn cylinder(radius: f32, height: f32) -> Self {
+        Sdf::Cylinder { radius, height }
+    }
+
+    pub fn plane(normal: Vector3<f32>, offset: f32) -> Self {
+        Sdf::Plane {
+            normal: normal.normalize(),
+            offset,
+        }
+    }
+
+    pub fn union(self, other: Sdf) -> Self {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Self {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn difference(self, other: Sdf) -> Self {
+        Sdf::Difference(Box::new(self), Box::new(other))
+    }
+
+    pub fn smooth_union(self, other: Sdf, k: f32) -> Self {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_intersection(self, other: Sdf, k: f32) -> Self {
+        Sdf::SmoothIntersection(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn smooth_difference(self, other: Sdf, k: f32) -> Self {
+        Sdf::SmoothDifference(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn translate(self, x: f32, y: f32, z: f32) -> Self {
+        Sdf::Translate(Vector3::new(x, y, z), Box::new(self))
+    }
+
+    pub fn rotate(self, axis: Vector3<f32>, angle: f32) -> Self {
+        Sdf::Rotate(
+            Quaternion::from_axis_angle(axis.normalize(), Rad(angle)),
+            Box::new(self),
+        )
+    }
+
+    pub fn scale(self, s: f32) -> Self {
+        Sdf::Scale(s, Box::new(self))
+    }
+
+    pub fn twist(self, k: f32) -> Self {
+        Sdf::Twist(k, Box::new(self))
+    }
+
+    pub fn bend(self, k: f32) -> Self {
+        Sdf::Bend(k, Box::new(self))
+    }
+
+    pub fn eval(&self, p: &Point3<f32>) -> f32 {
+        match self {
+            Sdf::Sphere { radius } => p.to_vec().magnitude() - radius,
+            Sdf::Box { size } => box_distance(p, size),
+            Sdf::RoundBox { size, radius } => {
+                box_distance(p, &(size - Vector3::from_value(*radius))) - radius
+            }
+            Sdf::Torus { major, minor } => {
+                Vector2::new(Vector2::new(p.x, p.z).magnitude() - major, p.y).magnitude() - minor
+            }
+            Sdf::Capsule { a, b, radius } => {
+                let pa = p - a;
+                let ba = b - a;
+                let h = (pa.dot(ba) / ba.magnitude2().max(1e-12)).clamp(0.0, 1.0);
+                (pa - ba * h).magnitude() - radius
+            }
+            Sdf::Cylinder { radius, height } => {
+                let d = Vector2::new(
+                    Vector2::new(p.x, p.z).magnitude() - radius,
+                    p.y.abs() - height,
+                );
+                d.x.max(d.y).min(0.0) + Vector2::new(d.x.max(0.0), d.y.max(0.0)).magnitude()
+            }
+            Sdf::Plane { normal, offset } => p.to_vec().dot(*normal) - offset,
+
+            Sdf::Union(a, b) => a.eval(p).min(b.eval(p)),
+            Sdf::Intersection(a, b) => a.eval(p).max(b.eval(p)),
+            Sdf::Difference(a, b) => a.eval(p).max(-b.eval(p)),
+            Sdf::SmoothUnion(a, b, k) => smin(a.eval(p), b.eval(p), *k),
+            Sdf::SmoothIntersection(a, b, k) => -smin(-a.eval(p), -b.eval(p), *k),
+            Sdf::SmoothDifference(a, b, k) => -smin(-a.eval(p), b.eval(p), *k),
+
+            // the child is evaluated at the point moved back into its own frame
+            Sdf::Translate(v, a) => a.eval(&(p - v)),
+            Sdf::Rotate(q, a) => a.eval(&Point3::from_vec(q.invert().rotate_vector(p.to_vec()))),
+            Sdf::Scale(s, a) => a.eval(&(p / *s)) * s,
+            // twist and bend distort the space, so the distance is only approximate
+            Sdf::Twist(k, a) => {
+                let (s, c) = (k * p.y).sin_cos();
+                a.eval(&Point3::new(c * p.x + s * p.z, p.y, -s * p.x + c * p.z))
+            }
+            Sdf::Bend(k, a) => {
+                let (s, c) = (k * p.x).sin_cos();
+                a.eval(&Point3::new(c * p.x + s * p.y, -s * p.x + c * p.y, p.z))
+            }
+        }
+    }
+}
+
+// example models, all within the unit cube
+pub fn preset(name: &str) -> Option<Sdf> {
+    let x = Vector3::unit_x();
+    let z = Vector3::unit_z();
+    let quarter = std::f32::consts::FRAC_PI_2;
+    Some(match name {
+        // the classic CSG example, a rounded cube and a sphere drilled along three axes
+        "csg" => {
+            let drill = Sdf::cylinder(0.3, 1.0)
+                .union(Sdf::cylinder(0.3, 1.0).rotate(x, quarter))
+                .union(Sdf::cylinder(0.3, 1.0).rotate(z, quarter));
+            Sdf::round_box(0.6, 0.6, 0.6, 0.08)
+                .intersection(Sdf::sphere(0.8))
+                .difference(drill)
+        }
+        "blend" => Sdf::sphere(0.35)
+            .translate(-0.3, 0.0, 0.0)
+            .smooth_union(Sdf::sphere(0.25).translate(0.35, 0.15, 0.0), 0.25)
+            .smooth_union(Sdf::torus(0.45, 0.08).rotate(x, 0.4), 0.15)
+            .smooth_difference(Sdf::sphere(0.2).translate(-0.3, 0.2, 0.25), 0.1),
+        "cut" => Sdf::sphere(0.7)
+            .smooth_intersection(Sdf::plane(Vector3::unit_y(), 0.35), 0.05)
+            .smooth_difference(
+                Sdf::capsule(
+                    Point3::new(-0.8, 0.35, 0.0),
+                    Point3::new(0.8, 0.35, 0.0),
+                    0.2,
+                ),
+                0.1,
+            ),
+        // sharp edges only, for comparing how the polygonizers keep them
+        "boxes" => Sdf::cuboid(0.5, 0.5, 0.5)
+            .rotate(Vector3::unit_y(), 0.4)
+            .difference(Sdf::cuboid(0.25, 0.25, 0.8))
+            .union(Sdf::cuboid(0.15, 0.8, 0.15)),
+        "twist" => Sdf::round_box(0.3, 0.8, 0.3, 0.05).twist(2.0),
+        "bend" => Sdf::round_box(0.7, 0.1, 0.25, 0.05)
+            .bend(1.2)
+            .scale(1.1)
+            .translate(0.0, -0.2, 0.0),
+        _ => return None,
+    })
+}
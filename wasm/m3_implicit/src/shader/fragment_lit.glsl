@@ -0,0 +1,16 @@
+#version 300 es
+
+precision highp float;
+
+in vec4 vertexColor;
+in vec3 vertexNormal;
+out vec4 fragmentColor;
+
+const vec3 lightDir = vec3(0.4, 0.8, 0.45);
+
+void main() {
+  // two-sided lighting, the surface is open where it leaves the grid
+  float intensity = abs( dot( normalize( vertexNormal ), normalize( lightDir ) ) );
+  intensity = clamp( intensity, 0.15, 1.0 );
+  fragmentColor = vertexColor * vec4( intensity, intensity, intensity, 1.0 );
+}
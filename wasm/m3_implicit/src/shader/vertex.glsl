@@ -6,10 +6,8 @@ layout(location = 1) in vec4 color;
 uniform mat4 mvpMatrix;
 
 out vec4 vertexColor;
-out vec3 vertexPos;
 
 void main() {
     vertexColor = color;
     gl_Position = mvpMatrix * vec4(position, 1.0);
-    vertexPos = gl_Position.xyz;
 }